@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS events (
+    id UUID PRIMARY KEY,
+    event_type VARCHAR(255) NOT NULL,
+    source VARCHAR(255) NOT NULL,
+    data JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_created_at ON events (created_at);
+CREATE INDEX IF NOT EXISTS idx_events_event_type_created_at ON events (event_type, created_at);
+CREATE INDEX IF NOT EXISTS idx_events_source_created_at ON events (source, created_at);
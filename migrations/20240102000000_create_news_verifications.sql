@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS news_verifications (
+    article_id UUID PRIMARY KEY,
+    credibility_score REAL NOT NULL,
+    ai_analysis JSONB NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_timestamp TIMESTAMPTZ NOT NULL,
+    smart_contract_state TEXT NOT NULL,
+    verification_timestamp TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
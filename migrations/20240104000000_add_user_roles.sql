@@ -0,0 +1,5 @@
+-- Users inserted without an explicit role get no access until an admin
+-- assigns one.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'unassigned'
+        CHECK (role IN ('admin', 'analyst', 'ingest', 'news_reviewer', 'unassigned'));
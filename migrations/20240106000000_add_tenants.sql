@@ -0,0 +1,92 @@
+CREATE TABLE IF NOT EXISTS tenants (
+    id UUID PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Rows written before tenancy existed belong to a single default tenant.
+INSERT INTO tenants (id, name)
+VALUES ('00000000-0000-0000-0000-000000000001', 'Default')
+ON CONFLICT (id) DO NOTHING;
+
+-- API keys were already issued with a tenant id; make sure each one exists.
+INSERT INTO tenants (id, name)
+SELECT DISTINCT tenant_id, 'Tenant ' || tenant_id FROM api_keys
+ON CONFLICT (id) DO NOTHING;
+
+ALTER TABLE api_keys
+    ADD CONSTRAINT api_keys_tenant_id_fkey FOREIGN KEY (tenant_id) REFERENCES tenants (id);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants (id);
+UPDATE users SET tenant_id = '00000000-0000-0000-0000-000000000001' WHERE tenant_id IS NULL;
+ALTER TABLE users ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_users_tenant_id ON users (tenant_id);
+
+ALTER TABLE events ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants (id);
+UPDATE events SET tenant_id = '00000000-0000-0000-0000-000000000001' WHERE tenant_id IS NULL;
+ALTER TABLE events ALTER COLUMN tenant_id SET NOT NULL;
+
+-- Every events query is scoped to one tenant, so lead each index with it.
+DROP INDEX IF EXISTS idx_events_created_at;
+DROP INDEX IF EXISTS idx_events_event_type_created_at;
+DROP INDEX IF EXISTS idx_events_source_created_at;
+CREATE INDEX IF NOT EXISTS idx_events_tenant_created_at ON events (tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_events_tenant_event_type_created_at
+    ON events (tenant_id, event_type, created_at);
+CREATE INDEX IF NOT EXISTS idx_events_tenant_source_created_at
+    ON events (tenant_id, source, created_at);
+
+-- Events are only visible to, and can only be written for, the tenant set
+-- with `set_config('app.tenant_id', ...)` in the current transaction.
+-- Without that setting every query fails rather than seeing all tenants.
+-- FORCE applies the policy to the table owner too; only superusers and
+-- BYPASSRLS roles are exempt.
+ALTER TABLE events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE events FORCE ROW LEVEL SECURITY;
+CREATE POLICY events_tenant_isolation ON events
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+-- Verifications were global; results recorded so far belong to the default
+-- tenant, and each tenant now keeps its own result per article.
+ALTER TABLE news_verifications ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants (id);
+UPDATE news_verifications
+SET tenant_id = '00000000-0000-0000-0000-000000000001'
+WHERE tenant_id IS NULL;
+ALTER TABLE news_verifications ALTER COLUMN tenant_id SET NOT NULL;
+ALTER TABLE news_verifications DROP CONSTRAINT IF EXISTS news_verifications_pkey;
+ALTER TABLE news_verifications ADD PRIMARY KEY (tenant_id, article_id);
+
+ALTER TABLE news_verifications ENABLE ROW LEVEL SECURITY;
+ALTER TABLE news_verifications FORCE ROW LEVEL SECURITY;
+CREATE POLICY news_verifications_tenant_isolation ON news_verifications
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+-- Logins and API keys are resolved before the tenant is known, so users and
+-- api_keys may also be read across tenants while `app.credential_lookup` is
+-- on. Without a tenant their isolation policy matches nothing instead of
+-- failing, since both policies are checked together.
+DO $$
+DECLARE
+    credential_table TEXT;
+BEGIN
+    FOREACH credential_table IN ARRAY ARRAY['users', 'api_keys']
+    LOOP
+        EXECUTE format('ALTER TABLE %I ENABLE ROW LEVEL SECURITY', credential_table);
+        EXECUTE format('ALTER TABLE %I FORCE ROW LEVEL SECURITY', credential_table);
+        EXECUTE format(
+            'CREATE POLICY %I ON %I
+                USING (tenant_id = NULLIF(current_setting(''app.tenant_id'', true), '''')::uuid)
+                WITH CHECK (tenant_id = NULLIF(current_setting(''app.tenant_id'', true), '''')::uuid)',
+            credential_table || '_tenant_isolation', credential_table
+        );
+        EXECUTE format(
+            'CREATE POLICY %I ON %I FOR SELECT
+                USING (current_setting(''app.credential_lookup'', true) = ''on'')',
+            credential_table || '_credential_lookup', credential_table
+        );
+    END LOOP;
+END
+$$;
@@ -0,0 +1,6 @@
+-- Results recorded before thresholds existed were never classified
+ALTER TABLE news_verifications
+    ADD COLUMN IF NOT EXISTS verification_status TEXT NOT NULL DEFAULT 'under_review'
+        CHECK (verification_status IN ('pending', 'verified', 'rejected', 'under_review'));
+
+ALTER TABLE news_verifications ALTER COLUMN verification_status DROP DEFAULT;
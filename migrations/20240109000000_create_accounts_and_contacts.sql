@@ -0,0 +1,44 @@
+CREATE TABLE IF NOT EXISTS accounts (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    name VARCHAR(255) NOT NULL,
+    domain VARCHAR(255),
+    industry VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_accounts_tenant_created_at ON accounts (tenant_id, created_at, id);
+
+CREATE TABLE IF NOT EXISTS contacts (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    -- Contacts outlive the account they are filed under
+    account_id UUID REFERENCES accounts (id) ON DELETE SET NULL,
+    first_name VARCHAR(255) NOT NULL,
+    last_name VARCHAR(255) NOT NULL,
+    -- Stored trimmed and lowercased
+    email VARCHAR(255),
+    phone VARCHAR(255),
+    title VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contacts_tenant_created_at ON contacts (tenant_id, created_at, id);
+CREATE INDEX IF NOT EXISTS idx_contacts_account_id ON contacts (account_id);
+CREATE INDEX IF NOT EXISTS idx_contacts_tenant_email ON contacts (tenant_id, email);
+
+-- Isolated like `events`: only rows of the tenant set with
+-- `set_config('app.tenant_id', ...)` are visible or writable.
+ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE accounts FORCE ROW LEVEL SECURITY;
+CREATE POLICY accounts_tenant_isolation ON accounts
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+ALTER TABLE contacts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE contacts FORCE ROW LEVEL SECURITY;
+CREATE POLICY contacts_tenant_isolation ON contacts
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
@@ -0,0 +1,76 @@
+-- An identity is one person as seen through their identifiers; events and
+-- at most one contact hang off it. Identities are merged when an event or a
+-- contact shows that two of them share a person.
+CREATE TABLE IF NOT EXISTS identities (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    contact_id UUID REFERENCES contacts (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_identities_contact_id ON identities (contact_id);
+
+-- Values are stored normalized, so lookups are exact matches
+CREATE TABLE IF NOT EXISTS identifiers (
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    kind TEXT NOT NULL CHECK (kind IN ('email', 'user_id', 'anonymous_id', 'phone')),
+    value VARCHAR(255) NOT NULL,
+    identity_id UUID NOT NULL REFERENCES identities (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, kind, value)
+);
+
+CREATE INDEX IF NOT EXISTS idx_identifiers_identity_id ON identifiers (identity_id);
+
+-- Events stored before resolution existed, or carrying no identifier, stay unresolved
+ALTER TABLE events ADD COLUMN IF NOT EXISTS identity_id UUID REFERENCES identities (id);
+CREATE INDEX IF NOT EXISTS idx_events_tenant_identity_created_at
+    ON events (tenant_id, identity_id, created_at);
+
+-- Both tables are isolated like `contacts`
+ALTER TABLE identities ENABLE ROW LEVEL SECURITY;
+ALTER TABLE identities FORCE ROW LEVEL SECURITY;
+CREATE POLICY identities_tenant_isolation ON identities
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+ALTER TABLE identifiers ENABLE ROW LEVEL SECURITY;
+ALTER TABLE identifiers FORCE ROW LEVEL SECURITY;
+CREATE POLICY identifiers_tenant_isolation ON identifiers
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+-- Existing contacts join the graph through their email and phone. Contacts
+-- are only readable per tenant, so the backfill runs once for each.
+DO $$
+DECLARE
+    tenant UUID;
+BEGIN
+    FOR tenant IN SELECT id FROM tenants
+    LOOP
+        PERFORM set_config('app.tenant_id', tenant::text, true);
+
+        INSERT INTO identities (id, tenant_id, contact_id, created_at)
+        SELECT gen_random_uuid(), tenant_id, id, created_at FROM contacts;
+
+        INSERT INTO identifiers (tenant_id, kind, value, identity_id)
+        SELECT i.tenant_id, 'email', c.email, i.id
+        FROM identities i JOIN contacts c ON c.id = i.contact_id
+        WHERE c.email IS NOT NULL
+        ON CONFLICT DO NOTHING;
+
+        INSERT INTO identifiers (tenant_id, kind, value, identity_id)
+        SELECT tenant_id, 'phone', phone, id
+        FROM (
+            SELECT i.tenant_id, i.id,
+                CASE WHEN btrim(c.phone) LIKE '+%' THEN '+' ELSE '' END
+                    || regexp_replace(c.phone, '\D', '', 'g') AS phone
+            FROM identities i JOIN contacts c ON c.id = i.contact_id
+            WHERE c.phone ~ '\d'
+        ) AS normalized
+        ON CONFLICT DO NOTHING;
+    END LOOP;
+    PERFORM set_config('app.tenant_id', '', true);
+END
+$$;
@@ -0,0 +1,54 @@
+-- A contact keeps the identities of contacts merged into it, so that the
+-- merge can be undone
+DROP INDEX IF EXISTS idx_identities_contact_id;
+CREATE INDEX IF NOT EXISTS idx_identities_contact_id ON identities (contact_id);
+
+-- Pairs the duplicate scan scored at or above the threshold. The lower id
+-- is always contact_a_id, so each pair has a single row.
+CREATE TABLE IF NOT EXISTS contact_duplicates (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    contact_a_id UUID NOT NULL REFERENCES contacts (id) ON DELETE CASCADE,
+    contact_b_id UUID NOT NULL REFERENCES contacts (id) ON DELETE CASCADE,
+    score REAL NOT NULL,
+    reasons TEXT[] NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'dismissed')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (contact_a_id < contact_b_id),
+    UNIQUE (tenant_id, contact_a_id, contact_b_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_duplicates_tenant_status_score
+    ON contact_duplicates (tenant_id, status, score DESC);
+
+CREATE TABLE IF NOT EXISTS contact_merges (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    -- No foreign keys on the contacts: the history outlives both of them
+    survivor_id UUID NOT NULL,
+    merged_contact_id UUID NOT NULL,
+    -- The merged contact as it was, restored on undo
+    merged_contact JSONB NOT NULL,
+    -- Ids of the records repointed to the survivor, by kind
+    moved_records JSONB NOT NULL,
+    merged_by UUID NOT NULL REFERENCES users (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    undone_at TIMESTAMPTZ,
+    undone_by UUID REFERENCES users (id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_merges_tenant_created_at
+    ON contact_merges (tenant_id, created_at, id);
+
+ALTER TABLE contact_duplicates ENABLE ROW LEVEL SECURITY;
+ALTER TABLE contact_duplicates FORCE ROW LEVEL SECURITY;
+CREATE POLICY contact_duplicates_tenant_isolation ON contact_duplicates
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+ALTER TABLE contact_merges ENABLE ROW LEVEL SECURITY;
+ALTER TABLE contact_merges FORCE ROW LEVEL SECURITY;
+CREATE POLICY contact_merges_tenant_isolation ON contact_merges
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
@@ -0,0 +1,83 @@
+CREATE TABLE IF NOT EXISTS pipelines (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_pipelines_tenant_created_at ON pipelines (tenant_id, created_at, id);
+
+CREATE TABLE IF NOT EXISTS stages (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    pipeline_id UUID NOT NULL REFERENCES pipelines (id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    position INTEGER NOT NULL,
+    -- Deals entering a won or lost stage are closed
+    kind TEXT NOT NULL CHECK (kind IN ('open', 'won', 'lost')),
+    -- Percent chance of winning a deal in this stage
+    probability INTEGER CHECK (probability BETWEEN 0 AND 100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Deferred so that reordering can swap positions within a transaction
+    CONSTRAINT stages_pipeline_id_position_key UNIQUE (pipeline_id, position)
+        DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE TABLE IF NOT EXISTS deals (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    pipeline_id UUID NOT NULL REFERENCES pipelines (id),
+    stage_id UUID NOT NULL REFERENCES stages (id),
+    name VARCHAR(255) NOT NULL,
+    -- In the currency's minor unit, e.g. cents
+    expected_value BIGINT NOT NULL CHECK (expected_value >= 0),
+    -- ISO 4217 code
+    currency VARCHAR(3) NOT NULL,
+    expected_close_date DATE,
+    contact_id UUID REFERENCES contacts (id) ON DELETE SET NULL,
+    account_id UUID REFERENCES accounts (id) ON DELETE SET NULL,
+    stage_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deals_tenant_created_at ON deals (tenant_id, created_at, id);
+CREATE INDEX IF NOT EXISTS idx_deals_stage_id ON deals (stage_id);
+CREATE INDEX IF NOT EXISTS idx_deals_contact_id ON deals (contact_id);
+CREATE INDEX IF NOT EXISTS idx_deals_account_id ON deals (account_id);
+
+-- One row per stage a deal entered, starting with the one it was created in
+CREATE TABLE IF NOT EXISTS deal_stage_changes (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    deal_id UUID NOT NULL REFERENCES deals (id) ON DELETE CASCADE,
+    from_stage_id UUID REFERENCES stages (id) ON DELETE SET NULL,
+    to_stage_id UUID REFERENCES stages (id) ON DELETE SET NULL,
+    changed_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deal_stage_changes_deal_changed_at
+    ON deal_stage_changes (deal_id, changed_at);
+
+-- Every table is isolated like `contacts`
+DO $$
+DECLARE
+    tenant_table TEXT;
+BEGIN
+    FOREACH tenant_table IN ARRAY ARRAY['pipelines', 'stages', 'deals', 'deal_stage_changes']
+    LOOP
+        EXECUTE format('ALTER TABLE %I ENABLE ROW LEVEL SECURITY', tenant_table);
+        EXECUTE format('ALTER TABLE %I FORCE ROW LEVEL SECURITY', tenant_table);
+        EXECUTE format(
+            'CREATE POLICY %I ON %I
+                USING (tenant_id = current_setting(''app.tenant_id'')::uuid)
+                WITH CHECK (tenant_id = current_setting(''app.tenant_id'')::uuid)',
+            tenant_table || '_tenant_isolation', tenant_table
+        );
+    END LOOP;
+END
+$$;
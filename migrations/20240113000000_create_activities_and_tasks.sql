@@ -0,0 +1,68 @@
+-- Calls, meetings and notes logged against exactly one contact, account or deal
+CREATE TABLE IF NOT EXISTS activities (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    kind TEXT NOT NULL CHECK (kind IN ('call', 'meeting', 'note')),
+    subject VARCHAR(255),
+    body TEXT,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    contact_id UUID REFERENCES contacts (id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts (id) ON DELETE CASCADE,
+    deal_id UUID REFERENCES deals (id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls(contact_id, account_id, deal_id) = 1)
+);
+
+CREATE INDEX IF NOT EXISTS idx_activities_tenant_occurred_at
+    ON activities (tenant_id, occurred_at, id);
+CREATE INDEX IF NOT EXISTS idx_activities_contact_id ON activities (contact_id);
+CREATE INDEX IF NOT EXISTS idx_activities_account_id ON activities (account_id);
+CREATE INDEX IF NOT EXISTS idx_activities_deal_id ON activities (deal_id);
+
+-- Follow-ups assigned to a user, attached like activities
+CREATE TABLE IF NOT EXISTS tasks (
+    id UUID PRIMARY KEY,
+    tenant_id UUID NOT NULL REFERENCES tenants (id),
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    due_at TIMESTAMPTZ NOT NULL,
+    assignee_id UUID NOT NULL REFERENCES users (id),
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'completed')),
+    completed_at TIMESTAMPTZ,
+    -- Set once the overdue notification is published; cleared when the task is rescheduled
+    overdue_notified_at TIMESTAMPTZ,
+    -- Set while a notifier instance publishes the overdue notification, so other
+    -- instances skip the task without it staying locked during delivery. A claim
+    -- older than the notifier's lease is abandoned and may be taken over.
+    overdue_claimed_at TIMESTAMPTZ,
+    contact_id UUID REFERENCES contacts (id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts (id) ON DELETE CASCADE,
+    deal_id UUID REFERENCES deals (id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls(contact_id, account_id, deal_id) = 1)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tasks_tenant_due_at ON tasks (tenant_id, due_at, id);
+CREATE INDEX IF NOT EXISTS idx_tasks_assignee_open_due_at
+    ON tasks (assignee_id, due_at) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_tasks_overdue_pending
+    ON tasks (due_at) WHERE status = 'open' AND overdue_notified_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_tasks_contact_id ON tasks (contact_id);
+CREATE INDEX IF NOT EXISTS idx_tasks_account_id ON tasks (account_id);
+CREATE INDEX IF NOT EXISTS idx_tasks_deal_id ON tasks (deal_id);
+
+ALTER TABLE activities ENABLE ROW LEVEL SECURITY;
+ALTER TABLE activities FORCE ROW LEVEL SECURITY;
+CREATE POLICY activities_tenant_isolation ON activities
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
+
+ALTER TABLE tasks ENABLE ROW LEVEL SECURITY;
+ALTER TABLE tasks FORCE ROW LEVEL SECURITY;
+CREATE POLICY tasks_tenant_isolation ON tasks
+    USING (tenant_id = current_setting('app.tenant_id')::uuid)
+    WITH CHECK (tenant_id = current_setting('app.tenant_id')::uuid);
@@ -17,4 +17,7 @@ solc --abi --bin --overwrite -o build contracts/NewsVerification.sol
 echo "Generating contract ABI..."
 cat build/NewsVerification.abi | jq '.' > build/NewsVerification.json
 
+# The service embeds the ABI at compile time (see src/core/blockchain.rs)
+cp build/NewsVerification.json contracts/NewsVerification.json
+
 echo "Contract compilation completed successfully!" 
\ No newline at end of file
@@ -0,0 +1,144 @@
+use crate::{
+    api::events::validate_field,
+    error::{AppError, Result},
+    models::{Account, AccountRequest, Contact, Page, PageQuery, TenantId},
+    services::{accounts, contacts},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn create_account(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Json(request): Json<AccountRequest>,
+) -> Result<(StatusCode, Json<Account>)> {
+    validate_account(&request)?;
+    let account = accounts::create_account(&pool, tenant_id, &request).await?;
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+pub async fn list_accounts(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Account>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        accounts::list_accounts(&pool, tenant_id, &page).await?,
+    ))
+}
+
+pub async fn get_account(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(account_id): Path<Uuid>,
+) -> Result<Json<Account>> {
+    Ok(Json(
+        accounts::get_account(&pool, tenant_id, account_id).await?,
+    ))
+}
+
+pub async fn update_account(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(account_id): Path<Uuid>,
+    Json(request): Json<AccountRequest>,
+) -> Result<Json<Account>> {
+    validate_account(&request)?;
+    let account = accounts::update_account(&pool, tenant_id, account_id, &request).await?;
+    Ok(Json(account))
+}
+
+pub async fn delete_account(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(account_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    accounts::delete_account(&pool, tenant_id, account_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_account_contacts(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(account_id): Path<Uuid>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Contact>>> {
+    validate_page(&page)?;
+    // An empty page would not tell a missing account from one without contacts
+    accounts::get_account(&pool, tenant_id, account_id).await?;
+
+    let contacts = contacts::list_contacts(&pool, tenant_id, Some(account_id), &page).await?;
+    Ok(Json(contacts))
+}
+
+fn validate_account(request: &AccountRequest) -> Result<()> {
+    validate_field("name", &request.name)?;
+    validate_optional_field("domain", request.domain.as_deref())?;
+    validate_optional_field("industry", request.industry.as_deref())
+}
+
+/// Blank values are accepted and stored as NULL.
+pub fn validate_optional_field(name: &str, value: Option<&str>) -> Result<()> {
+    match value {
+        Some(value) if !value.trim().is_empty() => validate_field(name, value),
+        _ => Ok(()),
+    }
+}
+
+pub fn validate_page(page: &PageQuery) -> Result<()> {
+    if page.page() == 0 {
+        return Err(AppError::Validation("page starts at 1".to_string()));
+    }
+    if page.per_page() == 0 || page.per_page() > PageQuery::MAX_PER_PAGE {
+        return Err(AppError::Validation(format!(
+            "per_page must be between 1 and {}",
+            PageQuery::MAX_PER_PAGE
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_account() {
+        let request = |name: &str, domain: Option<&str>| AccountRequest {
+            name: name.to_string(),
+            domain: domain.map(str::to_string),
+            industry: None,
+        };
+
+        assert!(validate_account(&request("Acme", Some("acme.io"))).is_ok());
+        assert!(validate_account(&request("Acme", Some("  "))).is_ok());
+        assert!(matches!(
+            validate_account(&request(" ", None)),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_account(&request("Acme", Some(&"a".repeat(300)))),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_page() {
+        let page = |page, per_page| PageQuery {
+            page: Some(page),
+            per_page: Some(per_page),
+        };
+
+        assert!(validate_page(&PageQuery::default()).is_ok());
+        assert!(validate_page(&page(2, PageQuery::MAX_PER_PAGE)).is_ok());
+        assert!(validate_page(&page(0, 10)).is_err());
+        assert!(validate_page(&page(1, 0)).is_err());
+        assert!(validate_page(&page(1, PageQuery::MAX_PER_PAGE + 1)).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    api::accounts::{validate_optional_field, validate_page},
+    error::{AppError, Result},
+    models::{
+        Activity, ActivityRequest, CreateActivityRequest, Page, PageQuery, RecordLink, TenantId,
+        User,
+    },
+    services::activities,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Upper bound on free-text bodies of activities and tasks, in characters.
+pub const MAX_TEXT_LENGTH: usize = 10_000;
+
+pub async fn create_activity(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Json(request): Json<CreateActivityRequest>,
+) -> Result<(StatusCode, Json<Activity>)> {
+    validate_record(&request.record)?;
+    validate_activity(&request.activity)?;
+    let activity = activities::create_activity(&pool, tenant_id, &request, user.id).await?;
+    Ok((StatusCode::CREATED, Json(activity)))
+}
+
+/// Newest first; `?contact_id=`, `?account_id=` or `?deal_id=` narrow the
+/// list to one record's history.
+pub async fn list_activities(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(filter): Query<RecordLink>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Activity>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        activities::list_activities(&pool, tenant_id, &filter, &page).await?,
+    ))
+}
+
+pub async fn get_activity(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(activity_id): Path<Uuid>,
+) -> Result<Json<Activity>> {
+    Ok(Json(
+        activities::get_activity(&pool, tenant_id, activity_id).await?,
+    ))
+}
+
+pub async fn update_activity(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(activity_id): Path<Uuid>,
+    Json(request): Json<ActivityRequest>,
+) -> Result<Json<Activity>> {
+    validate_activity(&request)?;
+    let activity = activities::update_activity(&pool, tenant_id, activity_id, &request).await?;
+    Ok(Json(activity))
+}
+
+pub async fn delete_activity(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(activity_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    activities::delete_activity(&pool, tenant_id, activity_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Activities and tasks hang off exactly one record.
+pub fn validate_record(record: &RecordLink) -> Result<()> {
+    let linked = [record.contact_id, record.account_id, record.deal_id]
+        .iter()
+        .filter(|id| id.is_some())
+        .count();
+    if linked != 1 {
+        return Err(AppError::Validation(
+            "exactly one of contact_id, account_id and deal_id is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Blank values are accepted and stored as NULL, like optional fields.
+pub fn validate_text(name: &str, value: Option<&str>) -> Result<()> {
+    if value.is_some_and(|value| value.trim().chars().count() > MAX_TEXT_LENGTH) {
+        return Err(AppError::Validation(format!(
+            "{} must be at most {} characters",
+            name, MAX_TEXT_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+fn validate_activity(request: &ActivityRequest) -> Result<()> {
+    validate_optional_field("subject", request.subject.as_deref())?;
+    validate_text("body", request.body.as_deref())?;
+    let present = |value: Option<&str>| value.is_some_and(|value| !value.trim().is_empty());
+    if !present(request.subject.as_deref()) && !present(request.body.as_deref()) {
+        return Err(AppError::Validation(
+            "subject or body is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ActivityKind;
+
+    #[test]
+    fn test_validate_record() {
+        let contact = RecordLink {
+            contact_id: Some(Uuid::new_v4()),
+            ..RecordLink::default()
+        };
+        assert!(validate_record(&contact).is_ok());
+        assert!(matches!(
+            validate_record(&RecordLink::default()),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_record(&RecordLink {
+                deal_id: Some(Uuid::new_v4()),
+                ..contact
+            }),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_activity() {
+        let note = |subject: Option<&str>, body: Option<&str>| ActivityRequest {
+            kind: ActivityKind::Note,
+            subject: subject.map(str::to_string),
+            body: body.map(str::to_string),
+            occurred_at: None,
+        };
+
+        assert!(validate_activity(&note(None, Some("Asked for a demo"))).is_ok());
+        assert!(validate_activity(&note(Some("Intro call"), None)).is_ok());
+        assert!(matches!(
+            validate_activity(&note(Some(" "), None)),
+            Err(AppError::Validation(_))
+        ));
+        let long = "x".repeat(MAX_TEXT_LENGTH + 1);
+        assert!(matches!(
+            validate_activity(&note(None, Some(&long))),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
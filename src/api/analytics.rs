@@ -0,0 +1,283 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{AnalyticsQuery, AnalyticsResponse, TenantId, TimeInterval, TimeSeriesData},
+    services::analytics_cache::{self, AnalyticsCache},
+};
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Shared filter for every aggregation: the tenant, `[start_date, end_date)`
+/// plus the optional `event_types` / `sources` lists (a NULL array disables
+/// the filter). Row-level security enforces the tenant as well; filtering on
+/// it explicitly lets the planner use the tenant-leading indexes.
+const EVENT_FILTER: &str = r#"
+    tenant_id = $1
+    AND created_at >= $2
+    AND created_at < $3
+    AND ($4::text[] IS NULL OR event_type = ANY($4))
+    AND ($5::text[] IS NULL OR source = ANY($5))
+"#;
+
+/// Upper bound on the number of buckets a single query may produce.
+const MAX_BUCKETS: i64 = 10_000;
+
+const DEFAULT_TIMEZONE: &str = "UTC";
+
+pub async fn get_analytics(
+    State(pool): State<PgPool>,
+    State(cache): State<AnalyticsCache>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsResponse>> {
+    validate_query(&query)?;
+
+    let interval = query
+        .interval
+        .unwrap_or_else(|| interval_for_range(query.start_date, query.end_date));
+    validate_bucket_count(&query, interval)?;
+
+    let timezone = query.timezone.as_deref().unwrap_or(DEFAULT_TIMEZONE);
+
+    let cache_key = cache
+        .versioned_key(
+            analytics_cache::cache_key(tenant_id, &query, interval, timezone),
+            tenant_id,
+            query.end_date,
+        )
+        .await;
+    if let Some(key) = &cache_key {
+        if let Some(response) = cache.get(key).await {
+            return Ok(Json(response));
+        }
+    }
+
+    validate_timezone(&pool, timezone).await?;
+
+    let (events_by_type, events_by_source, time_series) = tokio::try_join!(
+        count_by(&pool, tenant_id, &query, "event_type"),
+        count_by(&pool, tenant_id, &query, "source"),
+        time_series(&pool, tenant_id, &query, interval, timezone),
+    )?;
+
+    let response = AnalyticsResponse {
+        total_events: events_by_type.values().sum(),
+        events_by_type,
+        events_by_source,
+        time_series,
+    };
+    if let Some(key) = &cache_key {
+        cache.put(key, query.end_date, &response).await;
+    }
+
+    Ok(Json(response))
+}
+
+fn validate_query(query: &AnalyticsQuery) -> Result<()> {
+    if query.start_date >= query.end_date {
+        return Err(AppError::Validation(
+            "start_date must be before end_date".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_bucket_count(query: &AnalyticsQuery, interval: TimeInterval) -> Result<()> {
+    let range = query.end_date - query.start_date;
+    let buckets = range.num_seconds() / interval.min_duration().num_seconds();
+    if buckets > MAX_BUCKETS {
+        return Err(AppError::Validation(format!(
+            "Range spans more than {} {} buckets; use a coarser interval",
+            MAX_BUCKETS,
+            interval.as_str()
+        )));
+    }
+    Ok(())
+}
+
+async fn validate_timezone(pool: &PgPool, timezone: &str) -> Result<()> {
+    if timezone == DEFAULT_TIMEZONE {
+        return Ok(());
+    }
+
+    let (known,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+            .bind(timezone)
+            .fetch_one(pool)
+            .await?;
+
+    if !known {
+        return Err(AppError::Validation(format!(
+            "Unknown timezone: {}",
+            timezone
+        )));
+    }
+    Ok(())
+}
+
+/// Picks a bucket size that keeps the series at a chartable length.
+fn interval_for_range(start: DateTime<Utc>, end: DateTime<Utc>) -> TimeInterval {
+    let range = end - start;
+    if range <= Duration::hours(2) {
+        TimeInterval::Minute
+    } else if range <= Duration::days(2) {
+        TimeInterval::Hour
+    } else if range <= Duration::days(90) {
+        TimeInterval::Day
+    } else if range <= Duration::days(730) {
+        TimeInterval::Week
+    } else {
+        TimeInterval::Month
+    }
+}
+
+/// `column` is always one of our own column names, never caller input.
+async fn count_by(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    query: &AnalyticsQuery,
+    column: &'static str,
+) -> Result<HashMap<String, i64>> {
+    let sql =
+        format!("SELECT {column}, COUNT(*) FROM events WHERE {EVENT_FILTER} GROUP BY {column}");
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let rows: Vec<(String, i64)> = sqlx::query_as(&sql)
+        .bind(tenant_id)
+        .bind(query.start_date)
+        .bind(query.end_date)
+        .bind(&query.event_types)
+        .bind(&query.sources)
+        .fetch_all(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(rows.into_iter().collect())
+}
+
+/// Buckets are truncated in the caller's time zone and every bucket in the
+/// range is emitted, with zero counts for gaps, so charts stay continuous.
+async fn time_series(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    query: &AnalyticsQuery,
+    interval: TimeInterval,
+    timezone: &str,
+) -> Result<Vec<TimeSeriesData>> {
+    let sql = format!(
+        r#"
+        WITH buckets AS (
+            SELECT generate_series(
+                date_trunc($6, $2 AT TIME ZONE $7),
+                date_trunc($6, ($3 - interval '1 microsecond') AT TIME ZONE $7),
+                ('1 ' || $6)::interval
+            ) AS local_bucket
+        ),
+        counts AS (
+            SELECT date_trunc($6, created_at AT TIME ZONE $7) AS local_bucket, COUNT(*) AS count
+            FROM events
+            WHERE {EVENT_FILTER}
+            GROUP BY 1
+        )
+        SELECT buckets.local_bucket AT TIME ZONE $7, COALESCE(counts.count, 0)
+        FROM buckets
+        LEFT JOIN counts USING (local_bucket)
+        ORDER BY buckets.local_bucket
+        "#
+    );
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let rows: Vec<(DateTime<Utc>, i64)> = sqlx::query_as(&sql)
+        .bind(tenant_id)
+        .bind(query.start_date)
+        .bind(query.end_date)
+        .bind(&query.event_types)
+        .bind(&query.sources)
+        .bind(interval.as_str())
+        .bind(timezone)
+        .fetch_all(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(timestamp, count)| TimeSeriesData { timestamp, count })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> AnalyticsQuery {
+        AnalyticsQuery {
+            start_date,
+            end_date,
+            event_types: None,
+            sources: None,
+            interval: None,
+            timezone: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_query_rejects_inverted_range() {
+        let now = Utc::now();
+        assert!(validate_query(&query(now - Duration::hours(1), now)).is_ok());
+        assert!(matches!(
+            validate_query(&query(now, now)),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_query(&query(now, now - Duration::hours(1))),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_query_string_filters() {
+        let uri: axum::http::Uri = "/api/v1/analytics?start_date=2024-01-01T00:00:00Z\
+            &end_date=2024-01-02T00:00:00Z&event_types=page_view,signup"
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<AnalyticsQuery>::try_from_uri(&uri).unwrap();
+
+        assert_eq!(
+            query.event_types,
+            Some(vec!["page_view".to_string(), "signup".to_string()])
+        );
+        assert!(query.sources.is_none());
+    }
+
+    #[test]
+    fn test_interval_for_range() {
+        let now = Utc::now();
+        let cases = [
+            (Duration::minutes(30), TimeInterval::Minute),
+            (Duration::days(1), TimeInterval::Hour),
+            (Duration::days(30), TimeInterval::Day),
+            (Duration::days(365), TimeInterval::Week),
+            (Duration::days(1000), TimeInterval::Month),
+        ];
+        for (range, expected) in cases {
+            assert_eq!(interval_for_range(now - range, now), expected);
+        }
+    }
+
+    #[test]
+    fn test_validate_bucket_count() {
+        let now = Utc::now();
+        let year = query(now - Duration::days(365), now);
+        assert!(validate_bucket_count(&year, TimeInterval::Hour).is_ok());
+        assert!(matches!(
+            validate_bucket_count(&year, TimeInterval::Minute),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    api::events::validate_field,
+    error::Result,
+    models::{ApiKey, CreateApiKeyRequest, CreateApiKeyResponse, User},
+    services::api_keys,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn create_api_key(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<User>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>)> {
+    validate_field("name", &request.name)?;
+    validate_field("source", &request.source)?;
+
+    let (api_key, key) = api_keys::create_api_key(
+        &pool,
+        request.name.trim(),
+        request.source.trim(),
+        admin.tenant_id,
+        admin.id,
+    )
+    .await?;
+
+    tracing::info!(api_key_id = %api_key.id, source = %api_key.source, admin_id = %admin.id, "API key created");
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse { key, api_key }),
+    ))
+}
+
+pub async fn list_api_keys(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<User>,
+) -> Result<Json<Vec<ApiKey>>> {
+    Ok(Json(api_keys::list_api_keys(&pool, admin.tenant_id).await?))
+}
+
+pub async fn revoke_api_key(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<User>,
+    Path(api_key_id): Path<Uuid>,
+) -> Result<Json<ApiKey>> {
+    let api_key = api_keys::revoke_api_key(&pool, admin.tenant_id, api_key_id).await?;
+
+    tracing::info!(api_key_id = %api_key.id, admin_id = %admin.id, "API key revoked");
+    Ok(Json(api_key))
+}
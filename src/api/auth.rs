@@ -0,0 +1,529 @@
+use crate::{
+    api::events::validate_field,
+    db,
+    error::{AppError, Result},
+    models::{
+        ApiKey, LoginRequest, LoginResponse, RefreshTokenRequest, RegisterRequest, Role, TenantId,
+        User,
+    },
+    services::{api_keys, auth::AuthService, sessions::SessionStore},
+};
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderName, StatusCode},
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+const USER_COLUMNS: &str = "id, tenant_id, email, name, role, created_at, updated_at";
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_FIELD_LENGTH: usize = 255;
+
+/// Header event producers present their API key in.
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Postgres `unique_violation`.
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Hash of a throwaway password with the default Argon2 parameters. Logins
+/// for unknown emails verify against it so they take as long as wrong
+/// passwords and do not reveal which emails are registered.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$vdHfx3o+KnQwWVeEp5x2DQ$I9h0ArSlZMr2Eft54PVh0EKdn8+noKxbTgUe9FGF0uU";
+
+/// Signs up a new organization: creates its tenant with the registrant as
+/// the first admin. Further users are added by that admin. Only routed when
+/// `auth.allow_public_registration` is set.
+pub async fn register(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthService>,
+    Json(request): Json<RegisterRequest>,
+) -> Result<(StatusCode, Json<User>)> {
+    validate_field("organization", &request.organization)?;
+    validate_new_user(&request.email, &request.name, &request.password)?;
+
+    let password_hash = auth.hash_password(&request.password).await?;
+
+    let tenant_id = Uuid::new_v4();
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO tenants (id, name) VALUES ($1, $2)")
+        .bind(tenant_id)
+        .bind(request.organization.trim())
+        .execute(&mut *tx)
+        .await?;
+    db::set_tenant(&mut tx, tenant_id).await?;
+    let user = insert_user(
+        &mut *tx,
+        tenant_id,
+        &request.email,
+        &request.name,
+        &password_hash,
+        Role::Admin,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+/// Inserts a validated user. Emails are unique across tenants since they
+/// identify the account at login.
+pub async fn insert_user(
+    executor: impl PgExecutor<'_>,
+    tenant_id: Uuid,
+    email: &str,
+    name: &str,
+    password_hash: &str,
+    role: Role,
+) -> Result<User> {
+    sqlx::query_as::<_, User>(&format!(
+        r#"
+        INSERT INTO users (id, tenant_id, email, name, password_hash, role)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(normalize_email(email))
+    .bind(name.trim())
+    .bind(password_hash)
+    .bind(role)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some(UNIQUE_VIOLATION) => {
+            AppError::Conflict("Email is already registered".to_string())
+        }
+        _ => AppError::Database(e),
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct UserCredentials {
+    #[sqlx(flatten)]
+    user: User,
+    password_hash: String,
+}
+
+pub async fn login(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthService>,
+    State(sessions): State<SessionStore>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>> {
+    let mut tx = db::begin_credential_lookup(&pool).await?;
+    let credentials = sqlx::query_as::<_, UserCredentials>(&format!(
+        "SELECT {}, password_hash FROM users WHERE LOWER(email) = $1",
+        USER_COLUMNS
+    ))
+    .bind(normalize_email(&request.email))
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    // Unknown emails and wrong passwords are indistinguishable to the caller
+    let invalid = || AppError::Auth("Invalid email or password".to_string());
+    let Some(credentials) = credentials else {
+        auth.verify_password(&request.password, DUMMY_PASSWORD_HASH)
+            .await?;
+        return Err(invalid());
+    };
+    if !auth
+        .verify_password(&request.password, &credentials.password_hash)
+        .await?
+    {
+        return Err(invalid());
+    }
+
+    let (session, refresh_token) = sessions.start(credentials.user.id).await?;
+    let token = auth.issue_token(&credentials.user, session.session_id)?;
+    Ok(Json(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: auth.token_ttl_seconds(),
+        user: credentials.user,
+    }))
+}
+
+/// Exchanges a refresh token for a new access token and a new refresh token;
+/// the presented one stops working.
+pub async fn refresh(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthService>,
+    State(sessions): State<SessionStore>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<Json<LoginResponse>> {
+    let (session, refresh_token) = sessions.rotate(&request.refresh_token).await?;
+
+    let Some(user) = find_user(&pool, session.user_id).await? else {
+        sessions.revoke(session.session_id).await?;
+        return Err(AppError::Auth("User no longer exists".to_string()));
+    };
+
+    let token = auth.issue_token(&user, session.session_id)?;
+    Ok(Json(LoginResponse {
+        token,
+        refresh_token,
+        expires_in: auth.token_ttl_seconds(),
+        user,
+    }))
+}
+
+/// Revokes the session behind the refresh token, which also invalidates any
+/// access tokens already issued for it.
+pub async fn logout(
+    State(sessions): State<SessionStore>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<StatusCode> {
+    sessions.end(&request.refresh_token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Makes the user with `email` an admin of their tenant. Run at startup for
+/// `auth.bootstrap_admin_email`; returns `None` when no such user exists.
+pub async fn bootstrap_admin(pool: &PgPool, email: &str) -> Result<Option<User>> {
+    let mut tx = db::begin_credential_lookup(pool).await?;
+    let Some(user) = sqlx::query_as::<_, User>(&format!(
+        "SELECT {} FROM users WHERE LOWER(email) = $1",
+        USER_COLUMNS
+    ))
+    .bind(normalize_email(email))
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    db::set_tenant(&mut tx, user.tenant_id).await?;
+    let user = sqlx::query_as::<_, User>(&format!(
+        "UPDATE users SET role = $2, updated_at = NOW() WHERE id = $1 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(user.id)
+    .bind(Role::Admin)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(user))
+}
+
+/// Looks the user up across tenants; callers check the tenant themselves.
+async fn find_user(pool: &PgPool, id: Uuid) -> Result<Option<User>> {
+    let mut tx = db::begin_credential_lookup(pool).await?;
+    let user =
+        sqlx::query_as::<_, User>(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS))
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+/// Rejects requests without a valid bearer token and makes the authenticated
+/// `User` available to handlers as `Extension<User>`. Requests carrying an
+/// `X-API-Key` header are authenticated by key instead and get an
+/// `Extension<ApiKey>`.
+pub async fn require_auth(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthService>,
+    State(sessions): State<SessionStore>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    if let Some(value) = request.headers().get(API_KEY_HEADER) {
+        let key = value
+            .to_str()
+            .map_err(|_| AppError::Auth("Malformed API key".to_string()))?;
+        let api_key = api_keys::authenticate(&pool, key.trim()).await?;
+        request.extensions_mut().insert(TenantId(api_key.tenant_id));
+        request.extensions_mut().insert(api_key);
+        return Ok(next.run(request).await);
+    }
+
+    let token = bearer_token(request.headers())?;
+    let claims = auth.validate_token(token)?;
+    if sessions.is_revoked(claims.sid).await? {
+        return Err(AppError::Auth("Session has been revoked".to_string()));
+    }
+
+    // Tokens outlive accounts, so the user is looked up on every request
+    let user = find_user(&pool, claims.sub)
+        .await?
+        .ok_or_else(|| AppError::Auth("User no longer exists".to_string()))?;
+    if user.tenant_id != claims.tenant_id {
+        return Err(AppError::Auth(
+            "Token was issued for another tenant".to_string(),
+        ));
+    }
+
+    request.extensions_mut().insert(TenantId(user.tenant_id));
+    request.extensions_mut().insert(user);
+    Ok(next.run(request).await)
+}
+
+/// Runs after `require_auth` and rejects users whose role does not grant
+/// `required`. API keys carry the `Ingest` role only. Attach with
+/// `middleware::from_fn(|req, next| require_role(Role::Analyst, req, next))`.
+pub async fn require_role(required: Role, request: Request, next: Next) -> Result<Response> {
+    if request.extensions().get::<ApiKey>().is_some() {
+        if required != Role::Ingest {
+            return Err(AppError::Forbidden(
+                "API keys may only submit events".to_string(),
+            ));
+        }
+        return Ok(next.run(request).await);
+    }
+
+    let user = request
+        .extensions()
+        .get::<User>()
+        .ok_or_else(|| AppError::Auth("Authentication required".to_string()))?;
+
+    if !user.role.grants(required) {
+        return Err(AppError::Forbidden(format!(
+            "This operation requires the {:?} role",
+            required
+        )));
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Result<&str> {
+    let value = headers
+        .get(AUTHORIZATION)
+        .ok_or_else(|| AppError::Auth("Missing bearer token".to_string()))?
+        .to_str()
+        .map_err(|_| AppError::Auth("Malformed authorization header".to_string()))?;
+
+    match value.split_once(' ') {
+        Some((scheme, token))
+            if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() =>
+        {
+            Ok(token.trim())
+        }
+        _ => Err(AppError::Auth("Malformed authorization header".to_string())),
+    }
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+pub fn validate_email(email: &str) -> Result<()> {
+    let email = email.trim();
+    let valid_email = email
+        .split_once('@')
+        .map(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        .unwrap_or(false);
+    if !valid_email || email.len() > MAX_FIELD_LENGTH {
+        return Err(AppError::Validation(
+            "A valid email is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_new_user(email: &str, name: &str, password: &str) -> Result<()> {
+    validate_email(email)?;
+
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_FIELD_LENGTH {
+        return Err(AppError::Validation(format!(
+            "name must be between 1 and {} characters",
+            MAX_FIELD_LENGTH
+        )));
+    }
+
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::Validation(format!(
+            "password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_new_user() {
+        assert!(validate_new_user("jane@example.com", "Jane", "s3cret-pass").is_ok());
+
+        for (email, name, password) in [
+            ("jane", "Jane", "s3cret-pass"),
+            ("@example.com", "Jane", "s3cret-pass"),
+            ("jane@example.com", " ", "s3cret-pass"),
+            ("jane@example.com", "Jane", "short"),
+        ] {
+            assert!(matches!(
+                validate_new_user(email, name, password),
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_dummy_password_hash_uses_default_params() {
+        let hash = argon2::PasswordHash::new(DUMMY_PASSWORD_HASH).unwrap();
+        let params = argon2::Params::try_from(&hash).unwrap();
+        let default = argon2::Params::default();
+
+        assert_eq!(hash.algorithm, argon2::Algorithm::default().ident());
+        assert_eq!(
+            (params.m_cost(), params.t_cost(), params.p_cost()),
+            (default.m_cost(), default.t_cost(), default.p_cost())
+        );
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email("  Jane@Example.COM "), "jane@example.com");
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+
+        assert_eq!(bearer_token(&headers("Bearer abc.def")).unwrap(), "abc.def");
+        assert_eq!(bearer_token(&headers("bearer abc.def")).unwrap(), "abc.def");
+        assert!(bearer_token(&headers("Basic abc")).is_err());
+        assert!(bearer_token(&headers("Bearer ")).is_err());
+        assert!(bearer_token(&HeaderMap::new()).is_err());
+    }
+
+    #[derive(Clone, axum::extract::FromRef)]
+    struct TestState {
+        db: PgPool,
+        auth: AuthService,
+        sessions: SessionStore,
+    }
+
+    /// Requests that fail token validation are rejected before the session
+    /// and user lookups, so a lazy pool and an unconnected Redis client are
+    /// enough here.
+    #[tokio::test]
+    async fn test_require_auth_rejects_missing_and_invalid_tokens() {
+        use axum::{body::Body, routing::get, Router};
+        use tower::ServiceExt;
+
+        let config = crate::config::AuthConfig {
+            jwt_secret: "test-secret".to_string(),
+            token_ttl_seconds: 3600,
+            refresh_token_ttl_seconds: 86400,
+            bootstrap_admin_email: None,
+            allow_public_registration: false,
+        };
+        let state = TestState {
+            db: PgPool::connect_lazy("postgres://localhost/unused").unwrap(),
+            auth: AuthService::new(&config),
+            sessions: SessionStore::new(
+                redis::Client::open("redis://localhost/").unwrap(),
+                &config,
+            ),
+        };
+        let app = Router::new()
+            .route("/protected", get(|| async { "OK" }))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                require_auth,
+            ))
+            .with_state(state);
+
+        for authorization in [None, Some("Bearer not-a-jwt")] {
+            let mut request = axum::http::Request::builder().uri("/protected");
+            if let Some(value) = authorization {
+                request = request.header(AUTHORIZATION, value);
+            }
+            let response = app
+                .clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_role() {
+        use axum::{body::Body, routing::get, Extension, Router};
+        use tower::ServiceExt;
+
+        let app = |required: Role, role: Role| {
+            Router::new()
+                .route("/protected", get(|| async { "OK" }))
+                .route_layer(axum::middleware::from_fn(move |req, next| {
+                    require_role(required, req, next)
+                }))
+                .layer(Extension(User {
+                    id: Uuid::new_v4(),
+                    tenant_id: Uuid::new_v4(),
+                    email: "jane@example.com".to_string(),
+                    name: "Jane".to_string(),
+                    role,
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                }))
+        };
+
+        let keyed = Router::new()
+            .route("/protected", get(|| async { "OK" }))
+            .route_layer(axum::middleware::from_fn(|req, next| {
+                require_role(Role::Analyst, req, next)
+            }))
+            .layer(Extension(ApiKey {
+                id: Uuid::new_v4(),
+                name: "Web tracker".to_string(),
+                source: "web".to_string(),
+                tenant_id: Uuid::new_v4(),
+                key_prefix: "ccrm_0123456".to_string(),
+                created_by: Uuid::new_v4(),
+                created_at: chrono::Utc::now(),
+                last_used_at: None,
+                revoked_at: None,
+            }));
+        let response = keyed
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/protected")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        for (required, role, expected) in [
+            (Role::Analyst, Role::Analyst, StatusCode::OK),
+            (Role::NewsReviewer, Role::Admin, StatusCode::OK),
+            (Role::NewsReviewer, Role::Analyst, StatusCode::FORBIDDEN),
+            (Role::Analyst, Role::Ingest, StatusCode::FORBIDDEN),
+        ] {
+            let response = app(required, role)
+                .oneshot(
+                    axum::http::Request::builder()
+                        .uri("/protected")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+    }
+}
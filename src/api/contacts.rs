@@ -0,0 +1,129 @@
+use crate::{
+    api::{
+        accounts::{validate_optional_field, validate_page},
+        auth::validate_email,
+        events::validate_field,
+    },
+    error::Result,
+    models::{Contact, ContactRequest, Event, Page, PageQuery, TenantId},
+    services::{contacts, identities},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn create_contact(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Json(request): Json<ContactRequest>,
+) -> Result<(StatusCode, Json<Contact>)> {
+    validate_contact(&request)?;
+    let contact = contacts::create_contact(&pool, tenant_id, &request).await?;
+    Ok((StatusCode::CREATED, Json(contact)))
+}
+
+pub async fn list_contacts(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Contact>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        contacts::list_contacts(&pool, tenant_id, None, &page).await?,
+    ))
+}
+
+pub async fn get_contact(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(contact_id): Path<Uuid>,
+) -> Result<Json<Contact>> {
+    Ok(Json(
+        contacts::get_contact(&pool, tenant_id, contact_id).await?,
+    ))
+}
+
+/// Replaces the contact, including its account link.
+pub async fn update_contact(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(contact_id): Path<Uuid>,
+    Json(request): Json<ContactRequest>,
+) -> Result<Json<Contact>> {
+    validate_contact(&request)?;
+    let contact = contacts::update_contact(&pool, tenant_id, contact_id, &request).await?;
+    Ok(Json(contact))
+}
+
+pub async fn delete_contact(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(contact_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    contacts::delete_contact(&pool, tenant_id, contact_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The contact's timeline: events of every identity resolved to it, newest
+/// first.
+pub async fn list_contact_events(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(contact_id): Path<Uuid>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Event>>> {
+    validate_page(&page)?;
+    // An empty page would not tell a missing contact from one without events
+    contacts::get_contact(&pool, tenant_id, contact_id).await?;
+    let events = identities::contact_timeline(&pool, tenant_id, contact_id, &page).await?;
+    Ok(Json(events))
+}
+
+fn validate_contact(request: &ContactRequest) -> Result<()> {
+    validate_field("first_name", &request.first_name)?;
+    validate_field("last_name", &request.last_name)?;
+    if let Some(email) = request.email.as_deref().filter(|e| !e.trim().is_empty()) {
+        validate_email(email)?;
+    }
+    validate_optional_field("phone", request.phone.as_deref())?;
+    validate_optional_field("title", request.title.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn request(email: Option<&str>) -> ContactRequest {
+        ContactRequest {
+            first_name: "Jane".to_string(),
+            last_name: "Doe".to_string(),
+            email: email.map(str::to_string),
+            phone: None,
+            title: None,
+            account_id: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_contact() {
+        assert!(validate_contact(&request(None)).is_ok());
+        assert!(validate_contact(&request(Some(""))).is_ok());
+        assert!(validate_contact(&request(Some("jane@example.com"))).is_ok());
+        assert!(matches!(
+            validate_contact(&request(Some("not-an-email"))),
+            Err(AppError::Validation(_))
+        ));
+
+        let mut unnamed = request(None);
+        unnamed.last_name = "  ".to_string();
+        assert!(matches!(
+            validate_contact(&unnamed),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    api::{accounts::validate_page, events::validate_field},
+    error::{AppError, Result},
+    models::{
+        CreateDealRequest, Deal, DealFilter, DealRequest, DealStageChange, MoveDealRequest, Page,
+        PageQuery, TenantId, User,
+    },
+    services::{
+        analytics_cache::AnalyticsCache, deals, event_publisher::EventPublisher,
+        identities::IdentityResolver,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn create_deal(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Json(request): Json<CreateDealRequest>,
+) -> Result<(StatusCode, Json<Deal>)> {
+    validate_deal(&request.deal)?;
+    let deal = deals::create_deal(&pool, tenant_id, &request, user.id).await?;
+    Ok((StatusCode::CREATED, Json(deal)))
+}
+
+pub async fn list_deals(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(filter): Query<DealFilter>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Deal>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        deals::list_deals(&pool, tenant_id, &filter, &page).await?,
+    ))
+}
+
+pub async fn get_deal(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(deal_id): Path<Uuid>,
+) -> Result<Json<Deal>> {
+    Ok(Json(deals::get_deal(&pool, tenant_id, deal_id).await?))
+}
+
+/// Replaces the deal's fields. Stage changes go through `move_deal`.
+pub async fn update_deal(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(deal_id): Path<Uuid>,
+    Json(request): Json<DealRequest>,
+) -> Result<Json<Deal>> {
+    validate_deal(&request)?;
+    let deal = deals::update_deal(&pool, tenant_id, deal_id, &request).await?;
+    Ok(Json(deal))
+}
+
+pub async fn delete_deal(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(deal_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    deals::delete_deal(&pool, tenant_id, deal_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Moves the deal to another stage of its pipeline and emits a
+/// `deal_stage_changed` event, as if it had been sent to `/api/v1/events`.
+#[allow(clippy::too_many_arguments)]
+pub async fn move_deal(
+    State(pool): State<PgPool>,
+    State(publisher): State<EventPublisher>,
+    State(cache): State<AnalyticsCache>,
+    State(resolver): State<IdentityResolver>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Path(deal_id): Path<Uuid>,
+    Json(request): Json<MoveDealRequest>,
+) -> Result<Json<Deal>> {
+    let (deal, event) = deals::move_deal(
+        &pool,
+        &resolver,
+        tenant_id,
+        deal_id,
+        request.stage_id,
+        user.id,
+    )
+    .await?;
+
+    cache.invalidate_live(tenant_id).await;
+    publisher.publish_all(std::slice::from_ref(&event)).await;
+
+    Ok(Json(deal))
+}
+
+/// Every stage the deal entered, oldest first.
+pub async fn list_deal_history(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(deal_id): Path<Uuid>,
+) -> Result<Json<Vec<DealStageChange>>> {
+    Ok(Json(deals::stage_history(&pool, tenant_id, deal_id).await?))
+}
+
+fn validate_deal(request: &DealRequest) -> Result<()> {
+    validate_field("name", &request.name)?;
+    if request.expected_value < 0 {
+        return Err(AppError::Validation(
+            "expected_value cannot be negative".to_string(),
+        ));
+    }
+    let currency = request.currency.trim();
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(
+            "currency must be a 3-letter ISO 4217 code".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(expected_value: i64, currency: &str) -> DealRequest {
+        DealRequest {
+            name: "Renewal".to_string(),
+            expected_value,
+            currency: currency.to_string(),
+            expected_close_date: None,
+            contact_id: None,
+            account_id: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_deal() {
+        assert!(validate_deal(&request(0, "USD")).is_ok());
+        assert!(validate_deal(&request(125_000, "eur")).is_ok());
+        assert!(matches!(
+            validate_deal(&request(-1, "USD")),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_deal(&request(100, "US")),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_deal(&request(100, "U$D")),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
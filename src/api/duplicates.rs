@@ -0,0 +1,78 @@
+use crate::{
+    api::accounts::validate_page,
+    error::Result,
+    models::{
+        Contact, ContactDuplicate, ContactMerge, DuplicateSuggestion, MergeContactsRequest, Page,
+        PageQuery, TenantId, User,
+    },
+    services::{contact_merges, duplicates},
+};
+use axum::{
+    extract::{Path, Query, State},
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Open duplicate suggestions with both contacts, most likely first.
+pub async fn list_duplicates(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<DuplicateSuggestion>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        duplicates::list_suggestions(&pool, tenant_id, &page).await?,
+    ))
+}
+
+pub async fn dismiss_duplicate(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(duplicate_id): Path<Uuid>,
+) -> Result<Json<ContactDuplicate>> {
+    Ok(Json(
+        duplicates::dismiss(&pool, tenant_id, duplicate_id).await?,
+    ))
+}
+
+/// Merges the contact named in the body into the one in the path.
+pub async fn merge_contacts(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Path(contact_id): Path<Uuid>,
+    Json(request): Json<MergeContactsRequest>,
+) -> Result<Json<ContactMerge>> {
+    let merge = contact_merges::merge_contacts(
+        &pool,
+        tenant_id,
+        contact_id,
+        request.merged_contact_id,
+        user.id,
+    )
+    .await?;
+    Ok(Json(merge))
+}
+
+pub async fn list_merges(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<ContactMerge>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        contact_merges::list_merges(&pool, tenant_id, &page).await?,
+    ))
+}
+
+/// Returns the restored contact.
+pub async fn undo_merge(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Path(merge_id): Path<Uuid>,
+) -> Result<Json<Contact>> {
+    let contact = contact_merges::undo_merge(&pool, tenant_id, merge_id, user.id).await?;
+    Ok(Json(contact))
+}
@@ -0,0 +1,357 @@
+use crate::{
+    error::{AppError, Result},
+    metrics,
+    models::{
+        ApiKey, BatchCreateEventsResponse, BatchItemResult, BatchItemStatus, CreateEventRequest,
+        Event, TenantId, User,
+    },
+    services::{
+        analytics_cache::AnalyticsCache,
+        event_publisher::EventPublisher,
+        event_store::{self, NewEvent},
+        identities::IdentityResolver,
+    },
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Matches the `VARCHAR(255)` columns of the `events` table.
+const MAX_FIELD_LENGTH: usize = 255;
+
+/// Upper bound on the number of events accepted by a single batch request.
+pub const MAX_BATCH_SIZE: usize = 10_000;
+
+/// Request body limit applied to the batch route, sized for `MAX_BATCH_SIZE` events.
+pub const MAX_BATCH_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+const NDJSON_CONTENT_TYPES: [&str; 2] = ["application/x-ndjson", "application/jsonl"];
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_event(
+    State(pool): State<PgPool>,
+    State(publisher): State<EventPublisher>,
+    State(cache): State<AnalyticsCache>,
+    State(resolver): State<IdentityResolver>,
+    Extension(tenant): Extension<TenantId>,
+    api_key: Option<Extension<ApiKey>>,
+    user: Option<Extension<User>>,
+    Json(mut request): Json<CreateEventRequest>,
+) -> Result<(StatusCode, Json<Event>)> {
+    stamp_source(&mut request, api_key.as_deref(), user.as_deref());
+    let event = event_store::insert_event(&pool, &resolver, new_event(request, tenant.0)?).await?;
+    let api_key_source = api_key.as_ref().map(|key| key.source.as_str());
+    metrics::record_events_ingested(api_key_source, "api", 1);
+
+    cache.invalidate_live(tenant.0).await;
+    publisher.publish_all(std::slice::from_ref(&event)).await;
+
+    Ok((StatusCode::CREATED, Json(event)))
+}
+
+/// Accepts either a JSON array or an NDJSON body of `CreateEventRequest`s.
+/// Items that fail to parse or validate are rejected individually; the rest
+/// are written with a single multi-row `UNNEST` insert.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_events_batch(
+    State(pool): State<PgPool>,
+    State(publisher): State<EventPublisher>,
+    State(cache): State<AnalyticsCache>,
+    State(resolver): State<IdentityResolver>,
+    Extension(tenant): Extension<TenantId>,
+    api_key: Option<Extension<ApiKey>>,
+    user: Option<Extension<User>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<BatchCreateEventsResponse>> {
+    let mut items = parse_batch(&headers, &body)?;
+    for request in items.iter_mut().filter_map(|(_, item)| item.as_mut().ok()) {
+        stamp_source(request, api_key.as_deref(), user.as_deref());
+    }
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut accepted = Vec::new();
+
+    for (index, item) in items {
+        match item.and_then(|request| new_event(request, tenant.0).map_err(|e| e.to_string())) {
+            Ok(event) => {
+                results.push(BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Accepted,
+                    id: Some(event.id),
+                    error: None,
+                });
+                accepted.push(event);
+            }
+            Err(error) => results.push(BatchItemResult {
+                index,
+                status: BatchItemStatus::Rejected,
+                id: None,
+                error: Some(error),
+            }),
+        }
+    }
+
+    let events = event_store::insert_events(&pool, &resolver, accepted).await?;
+    let api_key_source = api_key.as_ref().map(|key| key.source.as_str());
+    metrics::record_events_ingested(api_key_source, "api", events.len());
+    if !events.is_empty() {
+        cache.invalidate_live(tenant.0).await;
+    }
+    publisher.publish_all(&events).await;
+
+    let accepted = events.len();
+    Ok(Json(BatchCreateEventsResponse {
+        accepted,
+        rejected: results.len() - accepted,
+        results,
+    }))
+}
+
+/// The source is never taken from the body: events sent with an API key
+/// carry the key's source, and events sent by a signed-in user carry
+/// `user:<id>` so they cannot pass for another producer's.
+fn stamp_source(request: &mut CreateEventRequest, api_key: Option<&ApiKey>, user: Option<&User>) {
+    if let Some(api_key) = api_key {
+        request.source = api_key.source.clone();
+    } else if let Some(user) = user {
+        request.source = format!("user:{}", user.id);
+    }
+}
+
+/// Validates a request and assigns it an id.
+pub fn new_event(request: CreateEventRequest, tenant_id: Uuid) -> Result<NewEvent> {
+    validate_event(&request)?;
+
+    Ok(NewEvent {
+        id: Uuid::new_v4(),
+        tenant_id,
+        event_type: request.event_type.trim().to_string(),
+        source: request.source.trim().to_string(),
+        data: request.data,
+    })
+}
+
+pub fn validate_event(request: &CreateEventRequest) -> Result<()> {
+    validate_field("event_type", &request.event_type)?;
+    validate_field("source", &request.source)?;
+
+    if !request.data.is_object() {
+        return Err(AppError::Validation(
+            "data must be a JSON object".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn validate_field(name: &str, value: &str) -> Result<()> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::Validation(format!("{} must not be empty", name)));
+    }
+    if value.len() > MAX_FIELD_LENGTH {
+        return Err(AppError::Validation(format!(
+            "{} must be at most {} characters",
+            name, MAX_FIELD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+type BatchItem = std::result::Result<CreateEventRequest, String>;
+
+/// Pairs each item with its position in the body: the 0-based array index,
+/// or the 1-based line number for NDJSON, where blank lines are skipped but
+/// still counted.
+fn parse_batch(headers: &HeaderMap, body: &[u8]) -> Result<Vec<(usize, BatchItem)>> {
+    let is_ndjson = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            let mime = value.split(';').next().unwrap_or_default().trim();
+            NDJSON_CONTENT_TYPES
+                .iter()
+                .any(|candidate| mime.eq_ignore_ascii_case(candidate))
+        })
+        .unwrap_or(false);
+
+    let items: Vec<(usize, BatchItem)> = if is_ndjson {
+        let body = std::str::from_utf8(body)
+            .map_err(|e| AppError::Validation(format!("Body is not valid UTF-8: {}", e)))?;
+        body.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                let item = serde_json::from_str(line).map_err(|e| e.to_string());
+                (index + 1, item)
+            })
+            .collect()
+    } else {
+        let values: Vec<serde_json::Value> = serde_json::from_slice(body)
+            .map_err(|e| AppError::Validation(format!("Body must be a JSON array: {}", e)))?;
+        values
+            .into_iter()
+            .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+            .enumerate()
+            .collect()
+    };
+
+    if items.is_empty() {
+        return Err(AppError::Validation("Batch must not be empty".to_string()));
+    }
+    if items.len() > MAX_BATCH_SIZE {
+        return Err(AppError::Validation(format!(
+            "Batch must contain at most {} events",
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn event_request(event_type: &str, source: &str, data: serde_json::Value) -> CreateEventRequest {
+        CreateEventRequest {
+            event_type: event_type.to_string(),
+            source: source.to_string(),
+            data,
+        }
+    }
+
+    fn content_type(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_validate_event_accepts_valid_request() {
+        let request = event_request("page_view", "web", serde_json::json!({ "user_id": "123" }));
+        assert!(validate_event(&request).is_ok());
+    }
+
+    #[test]
+    fn test_validate_event_rejects_blank_fields() {
+        let request = event_request("  ", "web", serde_json::json!({}));
+        assert!(matches!(
+            validate_event(&request),
+            Err(AppError::Validation(_))
+        ));
+
+        let request = event_request("page_view", "", serde_json::json!({}));
+        assert!(matches!(
+            validate_event(&request),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_event_rejects_oversized_fields() {
+        let request = event_request(&"a".repeat(MAX_FIELD_LENGTH + 1), "web", serde_json::json!({}));
+        assert!(matches!(
+            validate_event(&request),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_event_rejects_non_object_data() {
+        let request = event_request("page_view", "web", serde_json::json!([1, 2, 3]));
+        assert!(matches!(
+            validate_event(&request),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_batch_json_array_rejects_items_individually() {
+        let body = serde_json::json!([
+            { "event_type": "page_view", "source": "web", "data": {} },
+            { "event_type": "page_view" },
+        ])
+        .to_string();
+
+        let items = parse_batch(&content_type("application/json"), body.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], (0, Ok(_))));
+        assert!(matches!(items[1], (1, Err(_))));
+    }
+
+    #[test]
+    fn test_parse_batch_ndjson() {
+        let body = concat!(
+            r#"{"event_type":"page_view","source":"web","data":{}}"#,
+            "\n",
+            "not json\n",
+            "\n",
+            r#"{"event_type":"signup","source":"web","data":{"plan":"pro"}}"#,
+        );
+
+        let items = parse_batch(
+            &content_type("application/x-ndjson; charset=utf-8"),
+            body.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0], (1, Ok(_))));
+        assert!(matches!(items[1], (2, Err(_))));
+        // The blank line keeps its number, so indexes match the caller's lines
+        assert_eq!(items[2].0, 4);
+        assert_eq!(items[2].1.as_ref().unwrap().event_type, "signup");
+    }
+
+    #[test]
+    fn test_parse_batch_rejects_empty_and_non_array_bodies() {
+        assert!(matches!(
+            parse_batch(&content_type("application/json"), b"[]"),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            parse_batch(&content_type("application/json"), b"{}"),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_stamp_source_ignores_body() {
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            name: "Web tracker".to_string(),
+            source: "web".to_string(),
+            tenant_id: Uuid::new_v4(),
+            key_prefix: "ccrm_0123456".to_string(),
+            created_by: Uuid::new_v4(),
+            created_at: chrono::Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+
+        let user = User {
+            id: Uuid::new_v4(),
+            tenant_id: api_key.tenant_id,
+            email: "ingest@example.com".to_string(),
+            name: "Ingest".to_string(),
+            role: crate::models::Role::Ingest,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+
+        let mut request = event_request("page_view", "ios", serde_json::json!({}));
+        stamp_source(&mut request, Some(&api_key), None);
+        assert_eq!(request.source, "web");
+
+        let mut request = event_request("page_view", "ios", serde_json::json!({}));
+        stamp_source(&mut request, None, Some(&user));
+        assert_eq!(request.source, format!("user:{}", user.id));
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{core::news_verification::NewsVerificationService, error::AppError};
+use axum::{extract::State, http::StatusCode, Json};
+use rdkafka::producer::{FutureProducer, Producer};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Upper bound per dependency, so one hung dependency cannot stall the probe
+/// past the orchestrator's own timeout.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Dependencies without which the instance cannot serve requests. The others
+/// are reported but only degrade news verification, so an outage of, say,
+/// the blockchain node does not take every instance out of rotation.
+const REQUIRED: &[&str] = &["postgres", "redis", "kafka"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<&'static str, DependencyHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyHealth {
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Liveness only says the process is serving requests. Dependencies are left
+/// out on purpose: restarting the instance does not fix a down database.
+pub async fn live() -> Json<HealthReport> {
+    Json(HealthReport {
+        status: HealthStatus::Up,
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Probes every dependency concurrently; answers 503 when a required one is
+/// down so the instance is taken out of rotation until it recovers.
+pub async fn ready(
+    State(pool): State<PgPool>,
+    State(redis): State<redis::Client>,
+    State(kafka): State<FutureProducer>,
+    State(news_verification): State<Arc<NewsVerificationService>>,
+) -> (StatusCode, Json<HealthReport>) {
+    let (postgres, redis, kafka, model, blockchain) = tokio::join!(
+        probe(check_postgres(&pool)),
+        probe(check_redis(&redis)),
+        probe(check_kafka(kafka)),
+        probe(news_verification.check_analyzer()),
+        probe(news_verification.check_ledger()),
+    );
+
+    let checks = BTreeMap::from([
+        ("postgres", postgres),
+        ("redis", redis),
+        ("kafka", kafka),
+        ("model", model),
+        ("blockchain", blockchain),
+    ]);
+    let status = overall_status(&checks);
+    let code = match status {
+        HealthStatus::Up => StatusCode::OK,
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (code, Json(HealthReport { status, checks }))
+}
+
+fn overall_status(checks: &BTreeMap<&'static str, DependencyHealth>) -> HealthStatus {
+    if checks
+        .iter()
+        .filter(|(name, _)| REQUIRED.contains(name))
+        .all(|(_, check)| check.status == HealthStatus::Up)
+    {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    }
+}
+
+async fn probe<E: std::fmt::Display>(
+    check: impl Future<Output = Result<(), E>>,
+) -> DependencyHealth {
+    let start = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {:?}", PROBE_TIMEOUT)),
+    };
+    DependencyHealth {
+        status: if error.is_none() {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        },
+        latency_ms,
+        error,
+    }
+}
+
+async fn check_postgres(pool: &PgPool) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+async fn check_redis(client: &redis::Client) -> Result<(), redis::RedisError> {
+    let mut connection = client.get_multiplexed_tokio_connection().await?;
+    redis::cmd("PING")
+        .query_async::<_, String>(&mut connection)
+        .await?;
+    Ok(())
+}
+
+/// Fetching cluster metadata needs a round trip to a broker, which is what
+/// the producer needs as well. librdkafka's call blocks, so it runs on the
+/// blocking pool.
+async fn check_kafka(producer: FutureProducer) -> Result<(), AppError> {
+    tokio::task::spawn_blocking(move || {
+        let metadata = producer.client().fetch_metadata(None, PROBE_TIMEOUT)?;
+        if metadata.brokers().is_empty() {
+            return Err(AppError::Internal("No Kafka brokers available".to_string()));
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Kafka probe failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        ai_model::StaticAnalyzer,
+        blockchain::StaticLedger,
+        news_verification::{CredibilityThresholds, InMemoryVerificationStore},
+    };
+    use axum::{body::Body, extract::FromRef, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[derive(Clone, FromRef)]
+    struct TestState {
+        db: PgPool,
+        redis: redis::Client,
+        kafka: FutureProducer,
+        news_verification: Arc<NewsVerificationService>,
+    }
+
+    async fn get_json(app: Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// Nothing listens on port 1, so the network probes fail fast and the
+    /// in-process ones pass.
+    #[tokio::test]
+    async fn test_ready_reports_each_dependency() {
+        let state = TestState {
+            db: PgPool::connect_lazy("postgres://localhost:1/unused").unwrap(),
+            redis: redis::Client::open("redis://localhost:1/").unwrap(),
+            kafka: rdkafka::ClientConfig::new()
+                .set("bootstrap.servers", "localhost:1")
+                .create()
+                .unwrap(),
+            news_verification: Arc::new(NewsVerificationService::new(
+                InMemoryVerificationStore::default(),
+                StaticAnalyzer,
+                StaticLedger,
+                CredibilityThresholds::default(),
+            )),
+        };
+        let app = Router::new()
+            .route("/health/live", get(live))
+            .route("/health/ready", get(ready))
+            .with_state(state);
+
+        let (status, body) = get_json(app.clone(), "/health/live").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "status": "up" }));
+
+        let (status, body) = get_json(app, "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "down");
+        for dependency in ["postgres", "redis", "kafka"] {
+            assert_eq!(
+                body["checks"][dependency]["status"], "down",
+                "{}",
+                dependency
+            );
+            assert!(body["checks"][dependency]["error"].is_string());
+        }
+        for dependency in ["model", "blockchain"] {
+            assert_eq!(body["checks"][dependency]["status"], "up", "{}", dependency);
+            assert!(body["checks"][dependency]["latency_ms"].is_u64());
+        }
+    }
+
+    #[test]
+    fn test_only_required_dependencies_fail_readiness() {
+        let check = |status| DependencyHealth {
+            status,
+            latency_ms: 0,
+            error: None,
+        };
+        let mut checks = BTreeMap::from([
+            ("postgres", check(HealthStatus::Up)),
+            ("redis", check(HealthStatus::Up)),
+            ("kafka", check(HealthStatus::Up)),
+            ("model", check(HealthStatus::Down)),
+            ("blockchain", check(HealthStatus::Down)),
+        ]);
+        assert_eq!(overall_status(&checks), HealthStatus::Up);
+
+        checks.insert("redis", check(HealthStatus::Down));
+        assert_eq!(overall_status(&checks), HealthStatus::Down);
+    }
+}
@@ -1,3 +1,14 @@
-pub mod events;
+pub mod accounts;
+pub mod activities;
 pub mod analytics;
-pub mod news; 
\ No newline at end of file
+pub mod api_keys;
+pub mod auth;
+pub mod contacts;
+pub mod deals;
+pub mod duplicates;
+pub mod events;
+pub mod health;
+pub mod news;
+pub mod pipelines;
+pub mod tasks;
+pub mod users;
\ No newline at end of file
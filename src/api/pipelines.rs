@@ -0,0 +1,147 @@
+use crate::{
+    api::events::validate_field,
+    error::{AppError, Result},
+    models::{PipelineDetail, PipelineRequest, TenantId},
+    services::pipelines,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Upper bound on the number of stages in one pipeline.
+const MAX_STAGES: usize = 50;
+
+pub async fn create_pipeline(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Json(request): Json<PipelineRequest>,
+) -> Result<(StatusCode, Json<PipelineDetail>)> {
+    validate_pipeline(&request)?;
+    let pipeline = pipelines::create_pipeline(&pool, tenant_id, &request).await?;
+    Ok((StatusCode::CREATED, Json(pipeline)))
+}
+
+pub async fn list_pipelines(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+) -> Result<Json<Vec<PipelineDetail>>> {
+    Ok(Json(pipelines::list_pipelines(&pool, tenant_id).await?))
+}
+
+pub async fn get_pipeline(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(pipeline_id): Path<Uuid>,
+) -> Result<Json<PipelineDetail>> {
+    Ok(Json(
+        pipelines::get_pipeline(&pool, tenant_id, pipeline_id).await?,
+    ))
+}
+
+/// Renames the pipeline and replaces its stage list.
+pub async fn update_pipeline(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(pipeline_id): Path<Uuid>,
+    Json(request): Json<PipelineRequest>,
+) -> Result<Json<PipelineDetail>> {
+    validate_pipeline(&request)?;
+    let pipeline = pipelines::update_pipeline(&pool, tenant_id, pipeline_id, &request).await?;
+    Ok(Json(pipeline))
+}
+
+pub async fn delete_pipeline(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(pipeline_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    pipelines::delete_pipeline(&pool, tenant_id, pipeline_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_pipeline(request: &PipelineRequest) -> Result<()> {
+    validate_field("name", &request.name)?;
+    if request.stages.is_empty() || request.stages.len() > MAX_STAGES {
+        return Err(AppError::Validation(format!(
+            "stages must have between 1 and {} entries",
+            MAX_STAGES
+        )));
+    }
+
+    let mut names = HashSet::new();
+    let mut ids = HashSet::new();
+    for stage in &request.stages {
+        validate_field("stage name", &stage.name)?;
+        if !names.insert(stage.name.trim().to_lowercase()) {
+            return Err(AppError::Validation(format!(
+                "Duplicate stage name: {}",
+                stage.name.trim()
+            )));
+        }
+        if let Some(id) = stage.id.filter(|id| !ids.insert(*id)) {
+            return Err(AppError::Validation(format!("Duplicate stage id: {}", id)));
+        }
+        if stage.probability.is_some_and(|p| !(0..=100).contains(&p)) {
+            return Err(AppError::Validation(
+                "probability must be between 0 and 100".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{StageKind, StageRequest};
+
+    fn stage(name: &str) -> StageRequest {
+        StageRequest {
+            id: None,
+            name: name.to_string(),
+            kind: StageKind::Open,
+            probability: None,
+        }
+    }
+
+    fn request(stages: Vec<StageRequest>) -> PipelineRequest {
+        PipelineRequest {
+            name: "Sales".to_string(),
+            stages,
+        }
+    }
+
+    #[test]
+    fn test_validate_pipeline() {
+        assert!(validate_pipeline(&request(vec![stage("Lead"), stage("Won")])).is_ok());
+        assert!(matches!(
+            validate_pipeline(&request(Vec::new())),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            validate_pipeline(&request(vec![stage("Lead"), stage(" lead ")])),
+            Err(AppError::Validation(_))
+        ));
+
+        let mut likely = stage("Proposal");
+        likely.probability = Some(101);
+        assert!(matches!(
+            validate_pipeline(&request(vec![likely])),
+            Err(AppError::Validation(_))
+        ));
+
+        let id = Uuid::new_v4();
+        let (mut first, mut second) = (stage("Lead"), stage("Demo"));
+        first.id = Some(id);
+        second.id = Some(id);
+        assert!(matches!(
+            validate_pipeline(&request(vec![first, second])),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
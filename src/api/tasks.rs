@@ -0,0 +1,140 @@
+use crate::{
+    api::{
+        accounts::validate_page,
+        activities::{validate_record, validate_text},
+        events::validate_field,
+    },
+    error::{AppError, Result},
+    models::{
+        CreateTaskRequest, Page, PageQuery, RecordLink, Task, TaskFilter, TaskRequest, TenantId,
+        UpcomingTasksQuery, User,
+    },
+    services::tasks,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn create_task(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Json(request): Json<CreateTaskRequest>,
+) -> Result<(StatusCode, Json<Task>)> {
+    validate_record(&request.record)?;
+    validate_task(&request.task)?;
+    let task = tasks::create_task(&pool, tenant_id, &request, user.id).await?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+/// Soonest due first; narrowed like activities, and by `?assignee_id=` and
+/// `?status=`.
+pub async fn list_tasks(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Query(record): Query<RecordLink>,
+    Query(filter): Query<TaskFilter>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Task>>> {
+    validate_page(&page)?;
+    Ok(Json(
+        tasks::list_tasks(&pool, tenant_id, &record, &filter, &page).await?,
+    ))
+}
+
+/// The caller's open tasks due within `?within_days=`, overdue ones first.
+pub async fn list_upcoming_tasks(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Extension(user): Extension<User>,
+    Query(query): Query<UpcomingTasksQuery>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Task>>> {
+    validate_page(&page)?;
+    validate_upcoming(&query)?;
+    Ok(Json(
+        tasks::upcoming_tasks(&pool, tenant_id, user.id, query.within_days(), &page).await?,
+    ))
+}
+
+pub async fn get_task(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<Task>> {
+    Ok(Json(tasks::get_task(&pool, tenant_id, task_id).await?))
+}
+
+/// Replaces the task, including its status: completing a task is an update
+/// with `"status": "completed"`.
+pub async fn update_task(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(task_id): Path<Uuid>,
+    Json(request): Json<TaskRequest>,
+) -> Result<Json<Task>> {
+    validate_task(&request)?;
+    let task = tasks::update_task(&pool, tenant_id, task_id, &request).await?;
+    Ok(Json(task))
+}
+
+pub async fn delete_task(
+    State(pool): State<PgPool>,
+    Extension(TenantId(tenant_id)): Extension<TenantId>,
+    Path(task_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    tasks::delete_task(&pool, tenant_id, task_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_task(request: &TaskRequest) -> Result<()> {
+    validate_field("title", &request.title)?;
+    validate_text("description", request.description.as_deref())
+}
+
+fn validate_upcoming(query: &UpcomingTasksQuery) -> Result<()> {
+    if query.within_days() == 0 || query.within_days() > UpcomingTasksQuery::MAX_WITHIN_DAYS {
+        return Err(AppError::Validation(format!(
+            "within_days must be between 1 and {}",
+            UpcomingTasksQuery::MAX_WITHIN_DAYS
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskStatus;
+    use chrono::Utc;
+
+    #[test]
+    fn test_validate_task() {
+        let mut request = TaskRequest {
+            title: "Send proposal".to_string(),
+            description: None,
+            due_at: Utc::now(),
+            assignee_id: None,
+            status: TaskStatus::Open,
+        };
+        assert!(validate_task(&request).is_ok());
+
+        request.title = " ".to_string();
+        assert!(matches!(
+            validate_task(&request),
+            Err(AppError::Validation(_))
+        ));
+
+        assert!(validate_upcoming(&UpcomingTasksQuery::default()).is_ok());
+        assert!(matches!(
+            validate_upcoming(&UpcomingTasksQuery {
+                within_days: Some(0)
+            }),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
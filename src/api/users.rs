@@ -0,0 +1,75 @@
+use crate::{
+    api::auth::{insert_user, validate_new_user},
+    db,
+    error::{AppError, Result},
+    models::{CreateUserRequest, UpdateUserRoleRequest, User},
+    services::auth::AuthService,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Adds a user to the admin's own tenant.
+pub async fn create_user(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthService>,
+    Extension(admin): Extension<User>,
+    Json(request): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<User>)> {
+    validate_new_user(&request.email, &request.name, &request.password)?;
+
+    let password_hash = auth.hash_password(&request.password).await?;
+    let mut tx = db::begin_tenant(&pool, admin.tenant_id).await?;
+    let user = insert_user(
+        &mut *tx,
+        admin.tenant_id,
+        &request.email,
+        &request.name,
+        &password_hash,
+        request.role,
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(user_id = %user.id, role = ?user.role, admin_id = %admin.id, "user created");
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+pub async fn update_user_role(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<User>,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<UpdateUserRoleRequest>,
+) -> Result<Json<User>> {
+    // Demoting yourself could leave nobody able to manage roles
+    if admin.id == user_id {
+        return Err(AppError::Validation(
+            "Administrators cannot change their own role".to_string(),
+        ));
+    }
+
+    // Users of other tenants are reported as missing rather than forbidden
+    let mut tx = db::begin_tenant(&pool, admin.tenant_id).await?;
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET role = $3, updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING id, tenant_id, email, name, role, created_at, updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(admin.tenant_id)
+    .bind(request.role)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("User {} not found", user_id)))?;
+    tx.commit().await?;
+
+    tracing::info!(user_id = %user.id, role = ?user.role, admin_id = %admin.id, "user role changed");
+    Ok(Json(user))
+}
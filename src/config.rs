@@ -1,5 +1,5 @@
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
@@ -7,6 +7,15 @@ pub struct Config {
     pub redis: RedisConfig,
     pub kafka: KafkaConfig,
     pub server: ServerConfig,
+    pub auth: AuthConfig,
+    pub blockchain: BlockchainConfig,
+    pub ai_model: AIModelConfig,
+    #[serde(default)]
+    pub identity: IdentityConfig,
+    #[serde(default)]
+    pub duplicates: DuplicatesConfig,
+    #[serde(default)]
+    pub tasks: TasksConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,7 +29,6 @@ pub struct DatabaseConfig {
 #[derive(Debug, Deserialize)]
 pub struct RedisConfig {
     pub url: String,
-    pub pool_size: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,27 +36,513 @@ pub struct KafkaConfig {
     pub brokers: Vec<String>,
     pub client_id: String,
     pub group_id: String,
+    #[serde(default = "default_events_topic")]
+    pub events_topic: String,
+    /// Topics carrying `CreateEventRequest` payloads; the consumer is disabled when empty.
+    #[serde(default)]
+    pub consumer_topics: Vec<String>,
+    #[serde(default = "default_dead_letter_topic")]
+    pub dead_letter_topic: String,
+    #[serde(default = "default_consumer_batch_size")]
+    pub consumer_batch_size: usize,
+    #[serde(default = "default_consumer_batch_timeout_ms")]
+    pub consumer_batch_timeout_ms: u64,
+    /// Where overdue task notifications are published.
+    #[serde(default = "default_task_notifications_topic")]
+    pub task_notifications_topic: String,
+}
+
+fn default_events_topic() -> String {
+    "core-crm.events".to_string()
+}
+
+fn default_dead_letter_topic() -> String {
+    "core-crm.events.dlq".to_string()
+}
+
+fn default_task_notifications_topic() -> String {
+    "core-crm.tasks.overdue".to_string()
+}
+
+fn default_consumer_batch_size() -> usize {
+    1000
+}
+
+fn default_consumer_batch_timeout_ms() -> u64 {
+    500
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Serve HTTPS instead of plain HTTP when set.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+}
+
+/// Where `/metrics` is served. It has a listener of its own so it is not
+/// exposed on the public API port.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 9100,
+        }
+    }
+}
+
+/// PEM-encoded certificate chain and private key.
+#[derive(Debug, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthConfig {
+    /// HMAC key for access tokens; at least 32 bytes.
+    pub jwt_secret: String,
+    /// Lifetime of access tokens. Kept short because clients renew them with
+    /// a refresh token rather than logging in again.
+    #[serde(default = "default_token_ttl_seconds")]
+    pub token_ttl_seconds: i64,
+    #[serde(default = "default_refresh_token_ttl_seconds")]
+    pub refresh_token_ttl_seconds: i64,
+    /// Existing user promoted to admin at startup, for tenants that have no
+    /// admin left to assign roles.
+    #[serde(default)]
+    pub bootstrap_admin_email: Option<String>,
+    /// Serves `/api/v1/auth/register`, which lets anyone create a tenant and
+    /// become its admin. Off unless the deployment offers self-service
+    /// sign-up.
+    #[serde(default)]
+    pub allow_public_registration: bool,
+}
+
+fn default_token_ttl_seconds() -> i64 {
+    15 * 60
+}
+
+fn default_refresh_token_ttl_seconds() -> i64 {
+    30 * 24 * 60 * 60
+}
+
+/// The placeholder older config files shipped with.
+const PLACEHOLDER_JWT_SECRET: &str = "change-me-in-production";
+
+/// HS256 keys shorter than the hash output weaken the signature.
+const MIN_JWT_SECRET_BYTES: usize = 32;
+
+impl AuthConfig {
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.jwt_secret.trim().is_empty() {
+            problems.push("auth.jwt_secret: must be set".to_string());
+        } else if self.jwt_secret == PLACEHOLDER_JWT_SECRET {
+            problems.push("auth.jwt_secret: must not be the placeholder value".to_string());
+        } else if self.jwt_secret.len() < MIN_JWT_SECRET_BYTES {
+            problems.push(format!(
+                "auth.jwt_secret: must be at least {} bytes, got {}",
+                MIN_JWT_SECRET_BYTES,
+                self.jwt_secret.len()
+            ));
+        }
+    }
+}
+
+/// Where verification proofs are anchored.
+#[derive(Debug, Deserialize)]
+pub struct BlockchainConfig {
+    pub rpc_url: String,
+    /// Checked against the node before the first call, so a misconfigured
+    /// URL cannot send transactions to the wrong network.
+    pub chain_id: u64,
+    pub contract_address: String,
+    /// Environment variable holding the hex signer key.
+    #[serde(default)]
+    pub signer_key_env: Option<String>,
+    /// File holding the hex signer key, e.g. a mounted secret. With neither
+    /// source set, transactions are sent from the node's first account.
+    #[serde(default)]
+    pub signer_key_file: Option<PathBuf>,
+    #[serde(default = "default_confirmations")]
+    pub confirmations: usize,
+}
+
+fn default_confirmations() -> usize {
+    1
+}
+
+impl BlockchainConfig {
+    /// Reads the signer key from its configured source. The key is kept out
+    /// of the config files themselves.
+    pub fn signer_key(&self) -> Result<Option<String>, String> {
+        let key = match (&self.signer_key_env, &self.signer_key_file) {
+            (None, None) => return Ok(None),
+            (Some(_), Some(_)) => {
+                return Err("set only one of signer_key_env and signer_key_file".to_string())
+            }
+            (Some(var), None) => std::env::var(var)
+                .map_err(|_| format!("environment variable {} is not set", var))?,
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?,
+        };
+
+        let key = key.trim();
+        let hex = key.trim_start_matches("0x");
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("signer key must be 32 hex-encoded bytes".to_string());
+        }
+        Ok(Some(key.to_string()))
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        if !(self.rpc_url.starts_with("http://") || self.rpc_url.starts_with("https://")) {
+            problems.push(format!(
+                "blockchain.rpc_url: expected an http(s) URL, got {:?}",
+                self.rpc_url
+            ));
+        }
+        if self.chain_id == 0 {
+            problems.push("blockchain.chain_id: must be non-zero".to_string());
+        }
+        let address = self.contract_address.trim_start_matches("0x");
+        if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+            problems.push(format!(
+                "blockchain.contract_address: expected a 20-byte hex address, got {:?}",
+                self.contract_address
+            ));
+        } else if address.chars().all(|c| c == '0') {
+            problems.push("blockchain.contract_address: must not be the zero address".to_string());
+        }
+        if let Err(e) = self.signer_key() {
+            problems.push(format!("blockchain signer key: {}", e));
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AIModelConfig {
+    pub model_path: PathBuf,
+    pub tokenizer_path: PathBuf,
+    /// Articles scoring at least this are marked verified.
+    #[serde(default = "default_verified_threshold")]
+    pub verified_threshold: f32,
+    /// Articles scoring below this are rejected; scores in between are left
+    /// for a reviewer.
+    #[serde(default = "default_rejected_threshold")]
+    pub rejected_threshold: f32,
+}
+
+fn default_verified_threshold() -> f32 {
+    0.7
+}
+
+fn default_rejected_threshold() -> f32 {
+    0.4
+}
+
+/// Where identifiers are looked up in `Event.data`, as dot-separated paths
+/// (`traits.email`). Every path of a kind is tried, and each value found is
+/// used.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IdentityConfig {
+    pub email: Vec<String>,
+    pub user_id: Vec<String>,
+    pub anonymous_id: Vec<String>,
+    pub phone: Vec<String>,
+}
+
+impl Default for IdentityConfig {
+    fn default() -> Self {
+        let paths = |paths: &[&str]| paths.iter().map(|path| path.to_string()).collect();
+        Self {
+            email: paths(&["email", "traits.email"]),
+            user_id: paths(&["user_id", "userId"]),
+            anonymous_id: paths(&["anonymous_id", "anonymousId"]),
+            phone: paths(&["phone", "traits.phone"]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DuplicatesConfig {
+    /// Seconds between scans of every tenant's contacts.
+    pub scan_interval_seconds: u64,
+    /// Pairs scoring below this are not suggested.
+    pub min_score: f32,
+}
+
+impl Default for DuplicatesConfig {
+    fn default() -> Self {
+        Self {
+            scan_interval_seconds: 60 * 60,
+            min_score: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TasksConfig {
+    /// Seconds between checks for newly overdue tasks.
+    pub overdue_check_interval_seconds: u64,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            overdue_check_interval_seconds: 60,
+        }
+    }
+}
+
+/// Every problem found while loading the configuration, so a bad deployment
+/// can be fixed in one pass instead of one restart per field.
+#[derive(Debug)]
+pub struct InvalidConfig(pub Vec<String>);
+
+impl fmt::Display for InvalidConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for InvalidConfig {}
+
 impl Config {
-    pub fn load() -> Result<Self, config::ConfigError> {
+    /// Layers `default.toml`, an optional `local.toml` and `APP_` environment
+    /// variables, with `__` separating nested keys
+    /// (`APP_BLOCKCHAIN__RPC_URL`), then validates the result. Lists are given
+    /// comma-separated (`APP_KAFKA__BROKERS=kafka-1:9092,kafka-2:9092`).
+    pub fn load() -> Result<Self, InvalidConfig> {
         let config_path = std::env::var("CONFIG_PATH")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("config"));
 
-        let config = config::Config::builder()
+        let source = config::Config::builder()
             .add_source(config::File::from(config_path.join("default.toml")))
             .add_source(config::File::from(config_path.join("local.toml")).required(false))
-            .add_source(config::Environment::with_prefix("APP"))
-            .build()?;
+            .add_source(environment())
+            .build()
+            .map_err(|e| InvalidConfig(vec![e.to_string()]))?;
+
+        Self::from_source(&source)
+    }
+
+    /// Deserializes each section on its own, so missing keys and type errors
+    /// in one section are reported together with those in the others and
+    /// with the validation problems of the sections that did parse.
+    fn from_source(source: &config::Config) -> Result<Self, InvalidConfig> {
+        let mut problems = Vec::new();
+
+        let database = section::<DatabaseConfig>(source, "database", &mut problems);
+        let redis = section::<RedisConfig>(source, "redis", &mut problems);
+        let kafka = section::<KafkaConfig>(source, "kafka", &mut problems);
+        let server = section::<ServerConfig>(source, "server", &mut problems);
+        let auth = section::<AuthConfig>(source, "auth", &mut problems);
+        let blockchain = section::<BlockchainConfig>(source, "blockchain", &mut problems);
+        let ai_model = section::<AIModelConfig>(source, "ai_model", &mut problems);
+        let identity = defaulted_section::<IdentityConfig>(source, "identity", &mut problems);
+        let duplicates = defaulted_section::<DuplicatesConfig>(source, "duplicates", &mut problems);
+        let tasks = defaulted_section::<TasksConfig>(source, "tasks", &mut problems);
+
+        if let Some(auth) = &auth {
+            auth.validate(&mut problems);
+        }
+        if let Some(blockchain) = &blockchain {
+            blockchain.validate(&mut problems);
+        }
+        if let Some(ai_model) = &ai_model {
+            ai_model.validate(&mut problems);
+        }
+        if let Some(identity) = &identity {
+            identity.validate(&mut problems);
+        }
+        if let Some(duplicates) = &duplicates {
+            duplicates.validate(&mut problems);
+        }
+        if let Some(tasks) = &tasks {
+            tasks.validate(&mut problems);
+        }
+
+        match (
+            database, redis, kafka, server, auth, blockchain, ai_model, identity, duplicates, tasks,
+        ) {
+            (
+                Some(database),
+                Some(redis),
+                Some(kafka),
+                Some(server),
+                Some(auth),
+                Some(blockchain),
+                Some(ai_model),
+                Some(identity),
+                Some(duplicates),
+                Some(tasks),
+            ) if problems.is_empty() => Ok(Self {
+                database,
+                redis,
+                kafka,
+                server,
+                auth,
+                blockchain,
+                ai_model,
+                identity,
+                duplicates,
+                tasks,
+            }),
+            _ => Err(InvalidConfig(problems)),
+        }
+    }
+}
+
+/// Keys read from the environment as comma-separated lists.
+const ENV_LIST_KEYS: [&str; 6] = [
+    "kafka.brokers",
+    "kafka.consumer_topics",
+    "identity.email",
+    "identity.user_id",
+    "identity.anonymous_id",
+    "identity.phone",
+];
+
+/// `APP_` variables, with numbers and booleans parsed so that they
+/// deserialize into non-string fields.
+fn environment() -> config::Environment {
+    ENV_LIST_KEYS.iter().fold(
+        config::Environment::with_prefix("APP")
+            .prefix_separator("_")
+            .separator("__")
+            .try_parsing(true)
+            .list_separator(","),
+        |environment, key| environment.with_list_parse_key(key),
+    )
+}
+
+fn section<T: serde::de::DeserializeOwned>(
+    source: &config::Config,
+    key: &str,
+    problems: &mut Vec<String>,
+) -> Option<T> {
+    source
+        .get(key)
+        .map_err(|e| problems.push(describe(key, e)))
+        .ok()
+}
+
+/// Like `section`, for sections whose fields all have defaults.
+fn defaulted_section<T: serde::de::DeserializeOwned + Default>(
+    source: &config::Config,
+    key: &str,
+    problems: &mut Vec<String>,
+) -> Option<T> {
+    match source.get(key) {
+        Ok(value) => Some(value),
+        Err(config::ConfigError::NotFound(_)) => Some(T::default()),
+        Err(e) => {
+            problems.push(describe(key, e));
+            None
+        }
+    }
+}
+
+/// Names the section, since errors from `get` only carry its key, if any.
+fn describe(key: &str, error: config::ConfigError) -> String {
+    match error {
+        config::ConfigError::NotFound(_) => format!("{}: missing", key),
+        config::ConfigError::Type {
+            unexpected,
+            expected,
+            ..
+        } => format!(
+            "{}: invalid type: {}, expected {}",
+            key, unexpected, expected
+        ),
+        e => format!("{}: {}", key, e),
+    }
+}
+
+impl AIModelConfig {
+    fn validate(&self, problems: &mut Vec<String>) {
+        for (field, path) in [
+            ("ai_model.model_path", &self.model_path),
+            ("ai_model.tokenizer_path", &self.tokenizer_path),
+        ] {
+            if !path.is_file() {
+                problems.push(format!("{}: no file at {}", field, path.display()));
+            }
+        }
+        for (field, threshold) in [
+            ("ai_model.verified_threshold", self.verified_threshold),
+            ("ai_model.rejected_threshold", self.rejected_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&threshold) {
+                problems.push(format!(
+                    "{}: must be between 0 and 1, got {}",
+                    field, threshold
+                ));
+            }
+        }
+        if self.rejected_threshold > self.verified_threshold {
+            problems.push(
+                "ai_model.rejected_threshold: must not exceed verified_threshold".to_string(),
+            );
+        }
+    }
+}
+
+impl IdentityConfig {
+    fn validate(&self, problems: &mut Vec<String>) {
+        for (field, paths) in [
+            ("identity.email", &self.email),
+            ("identity.user_id", &self.user_id),
+            ("identity.anonymous_id", &self.anonymous_id),
+            ("identity.phone", &self.phone),
+        ] {
+            for path in paths {
+                if path.split('.').any(|segment| segment.trim().is_empty()) {
+                    problems.push(format!("{}: invalid path {:?}", field, path));
+                }
+            }
+        }
+    }
+}
+
+impl DuplicatesConfig {
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.scan_interval_seconds == 0 {
+            problems.push("duplicates.scan_interval_seconds: must be non-zero".to_string());
+        }
+        if !(self.min_score > 0.0 && self.min_score <= 1.0) {
+            problems.push(format!(
+                "duplicates.min_score: must be above 0 and at most 1, got {}",
+                self.min_score
+            ));
+        }
+    }
+}
 
-        config.try_deserialize()
+impl TasksConfig {
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.overdue_check_interval_seconds == 0 {
+            problems.push("tasks.overdue_check_interval_seconds: must be non-zero".to_string());
+        }
     }
 }
 
@@ -63,17 +557,204 @@ impl Default for Config {
             },
             redis: RedisConfig {
                 url: "redis://localhost:6379".to_string(),
-                pool_size: 10,
             },
             kafka: KafkaConfig {
                 brokers: vec!["localhost:9092".to_string()],
                 client_id: "core-crm".to_string(),
                 group_id: "core-crm-group".to_string(),
+                events_topic: default_events_topic(),
+                consumer_topics: Vec::new(),
+                dead_letter_topic: default_dead_letter_topic(),
+                consumer_batch_size: default_consumer_batch_size(),
+                consumer_batch_timeout_ms: default_consumer_batch_timeout_ms(),
+                task_notifications_topic: default_task_notifications_topic(),
             },
             server: ServerConfig {
                 host: "127.0.0.1".to_string(),
                 port: 3000,
+                tls: None,
+                metrics: MetricsConfig::default(),
+            },
+            auth: AuthConfig {
+                jwt_secret: String::new(),
+                token_ttl_seconds: default_token_ttl_seconds(),
+                refresh_token_ttl_seconds: default_refresh_token_ttl_seconds(),
+                bootstrap_admin_email: None,
+                allow_public_registration: false,
+            },
+            blockchain: BlockchainConfig {
+                rpc_url: "http://localhost:8545".to_string(),
+                chain_id: 31337,
+                contract_address: "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+                signer_key_env: None,
+                signer_key_file: None,
+                confirmations: default_confirmations(),
+            },
+            ai_model: AIModelConfig {
+                model_path: PathBuf::from("models/news_verification.onnx"),
+                tokenizer_path: PathBuf::from("models/news_verification.tokenizer.json"),
+                verified_threshold: default_verified_threshold(),
+                rejected_threshold: default_rejected_threshold(),
             },
+            identity: IdentityConfig::default(),
+            duplicates: DuplicatesConfig::default(),
+            tasks: TasksConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_every_invalid_field() {
+        let mut config = Config::default();
+        config.auth.jwt_secret = "too-short".to_string();
+        config.blockchain.rpc_url = "localhost:8545".to_string();
+        config.blockchain.contract_address = "0x123...".to_string();
+        config.blockchain.signer_key_env = Some("CORE_CRM_TEST_UNSET_SIGNER_KEY".to_string());
+        config.ai_model.model_path = PathBuf::from("models/does_not_exist.onnx");
+        config.ai_model.rejected_threshold = 0.9;
+        config.identity.email.push("traits..email".to_string());
+        config.duplicates.min_score = 0.0;
+        config.tasks.overdue_check_interval_seconds = 0;
+
+        let mut problems = Vec::new();
+        config.auth.validate(&mut problems);
+        config.blockchain.validate(&mut problems);
+        config.ai_model.validate(&mut problems);
+        config.identity.validate(&mut problems);
+        config.duplicates.validate(&mut problems);
+        config.tasks.validate(&mut problems);
+        for field in [
+            "auth.jwt_secret",
+            "blockchain.rpc_url",
+            "blockchain.contract_address",
+            "blockchain signer key",
+            "ai_model.model_path",
+            "ai_model.rejected_threshold",
+            "identity.email",
+            "duplicates.min_score",
+            "tasks.overdue_check_interval_seconds",
+        ] {
+            assert!(
+                problems.iter().any(|problem| problem.starts_with(field)),
+                "no problem reported for {}: {:?}",
+                field,
+                problems
+            );
+        }
+        assert!(!problems
+            .iter()
+            .any(|p| p.starts_with("blockchain.chain_id")));
+    }
+
+    #[test]
+    fn test_load_reports_parse_errors_with_validation_problems() {
+        let source = config::Config::builder()
+            .add_source(config::File::from_str(
+                r#"
+                [server]
+                host = "127.0.0.1"
+                port = "http"
+
+                [blockchain]
+                rpc_url = "localhost:8545"
+                contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+
+                [duplicates]
+                min_score = 0.0
+                "#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+
+        let InvalidConfig(problems) = Config::from_source(&source).unwrap_err();
+        for expected in [
+            "database: missing",
+            "server: invalid type",
+            "blockchain: missing field `chain_id`",
+            "ai_model: missing",
+            "duplicates.min_score",
+        ] {
+            assert!(
+                problems.iter().any(|problem| problem.starts_with(expected)),
+                "no problem reported for {}: {:?}",
+                expected,
+                problems
+            );
+        }
+    }
+
+    #[test]
+    fn test_jwt_secret_must_be_set_and_long_enough() {
+        let mut auth = Config::default().auth;
+        for secret in [
+            "",
+            "change-me-in-production",
+            "31-bytes-is-one-short-of-enough",
+        ] {
+            auth.jwt_secret = secret.to_string();
+            let mut problems = Vec::new();
+            auth.validate(&mut problems);
+            assert_eq!(problems.len(), 1, "{:?} accepted", secret);
         }
+
+        auth.jwt_secret = "a".repeat(32);
+        let mut problems = Vec::new();
+        auth.validate(&mut problems);
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn test_environment_parses_lists_and_numbers() {
+        let variables = [
+            ("APP_KAFKA__BROKERS", "kafka-1:9092,kafka-2:9092"),
+            ("APP_KAFKA__CONSUMER_TOPICS", "core-crm.events"),
+            ("APP_IDENTITY__EMAIL", "email,traits.email"),
+            ("APP_SERVER__PORT", "8080"),
+            ("APP_AUTH__JWT_SECRET", "not,a,list"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let source = config::Config::builder()
+            .add_source(environment().source(Some(variables)))
+            .build()
+            .unwrap();
+
+        let list = |key: &str| source.get::<Vec<String>>(key).unwrap();
+        assert_eq!(list("kafka.brokers"), ["kafka-1:9092", "kafka-2:9092"]);
+        assert_eq!(list("kafka.consumer_topics"), ["core-crm.events"]);
+        assert_eq!(list("identity.email"), ["email", "traits.email"]);
+        assert_eq!(source.get::<u16>("server.port").unwrap(), 8080);
+        assert_eq!(
+            source.get::<String>("auth.jwt_secret").unwrap(),
+            "not,a,list"
+        );
+    }
+
+    #[test]
+    fn test_signer_key_sources() {
+        let mut blockchain = Config::default().blockchain;
+        assert_eq!(blockchain.signer_key(), Ok(None));
+
+        let path = std::env::temp_dir().join(format!("signer-{}.key", uuid::Uuid::new_v4()));
+        std::fs::write(&path, format!("0x{}\n", "ab".repeat(32))).unwrap();
+        blockchain.signer_key_file = Some(path.clone());
+        assert_eq!(
+            blockchain.signer_key(),
+            Ok(Some(format!("0x{}", "ab".repeat(32))))
+        );
+
+        blockchain.signer_key_env = Some("CORE_CRM_TEST_SIGNER_KEY".to_string());
+        assert!(blockchain.signer_key().is_err());
+
+        std::fs::write(&path, "not-a-key").unwrap();
+        blockchain.signer_key_env = None;
+        assert!(blockchain.signer_key().is_err());
+        std::fs::remove_file(path).unwrap();
     }
-} 
\ No newline at end of file
+}
@@ -1,10 +1,15 @@
-use crate::error::{AppError, Result};
+use crate::{
+    error::{AppError, Result},
+    metrics,
+};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::{path::Path, sync::Arc, time::Instant};
 use tokenizers::Tokenizer;
+use tokio::sync::OnceCell;
 use tract_onnx::prelude::*;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIAnalysis {
     pub fact_check_score: f32,
     pub source_reliability: f32,
@@ -14,15 +19,30 @@ pub struct AIAnalysis {
     pub confidence_scores: std::collections::HashMap<String, f32>,
 }
 
+/// Scores article content for the verification pipeline.
+#[async_trait]
+pub trait ContentAnalyzer: Send + Sync {
+    async fn analyze_content(&self, content: &str) -> Result<AIAnalysis>;
+
+    /// Used by the readiness probe. Analyzers with nothing to load are ready
+    /// as soon as they exist.
+    async fn check_ready(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+/// ONNX news verification model run with tract.
 pub struct AIModel {
-    model: SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>,
-    tokenizer: Tokenizer,
-    device: Device,
+    model: Arc<Plan>,
+    tokenizer: Arc<Tokenizer>,
+    warmed_up: OnceCell<()>,
 }
 
 impl AIModel {
-    pub fn new(model_path: &str) -> Result<Self> {
-        let model_path = Path::new(model_path);
+    pub fn new(model_path: impl AsRef<Path>, tokenizer_path: impl AsRef<Path>) -> Result<Self> {
+        let model_path = model_path.as_ref();
         if !model_path.exists() {
             return Err(AppError::Internal(format!(
                 "Model not found at path: {}",
@@ -32,35 +52,43 @@ impl AIModel {
 
         // Load the ONNX model
         let model = tract_onnx::onnx()
-            .model_for_path(model_path)?
-            .into_optimized()?
-            .into_runnable()?;
+            .model_for_path(model_path)
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|e| AppError::Internal(format!("Failed to load model: {}", e)))?;
 
         // Load the tokenizer
-        let tokenizer = Tokenizer::from_file(model_path.with_extension("tokenizer.json"))
+        let tokenizer = Tokenizer::from_file(tokenizer_path)
             .map_err(|e| AppError::Internal(format!("Failed to load tokenizer: {}", e)))?;
 
         Ok(Self {
-            model,
-            tokenizer,
-            device: Device::Cpu,
+            model: Arc::new(model),
+            tokenizer: Arc::new(tokenizer),
+            warmed_up: OnceCell::new(),
         })
     }
 
-    pub async fn analyze_content(&self, content: &str) -> Result<AIAnalysis> {
+    fn infer(model: &Plan, tokenizer: &Tokenizer, content: &str) -> Result<AIAnalysis> {
         // Tokenize the input
-        let tokens = self.tokenizer
+        let tokens = tokenizer
             .encode(content, true)
             .map_err(|e| AppError::Internal(format!("Tokenization failed: {}", e)))?;
+        let ids = tokens.get_ids();
 
         // Prepare input tensor
-        let input = tract_ndarray::Array4::from_shape_fn((1, 1, tokens.len(), 1), |(_, _, i, _)| {
-            tokens.get_ids()[i] as f32
+        let input = tract_ndarray::Array4::from_shape_fn((1, 1, ids.len(), 1), |(_, _, i, _)| {
+            ids[i] as f32
         });
 
         // Run inference
-        let result = self.model.run(tvec!(input.into()))?;
-        let output = result[0].to_array_view::<f32>()?;
+        let result = model
+            .run(tvec!(input.into_tensor().into()))
+            .map_err(|e| AppError::Internal(format!("Inference failed: {}", e)))?;
+        let output = result[0]
+            .to_array_view::<f32>()
+            .and_then(|view| Ok(view.into_dimensionality::<tract_ndarray::Ix4>()?))
+            .map_err(|e| AppError::Internal(format!("Unexpected model output: {}", e)))?;
+        check_output_shape(output.shape())?;
 
         // Process the model output
         let fact_check_score = output[[0, 0, 0, 0]];
@@ -68,35 +96,99 @@ impl AIModel {
         let content_quality = output[[0, 0, 2, 0]];
         let bias_detection = output[[0, 0, 3, 0]];
 
-        // Extract entities using NER model
-        let entities = self.extract_entities(content)?;
-
         Ok(AIAnalysis {
             fact_check_score: fact_check_score.clamp(0.0, 1.0),
             source_reliability: source_reliability.clamp(0.0, 1.0),
             content_quality: content_quality.clamp(0.0, 1.0),
             bias_detection: bias_detection.clamp(0.0, 1.0),
-            detected_entities: entities,
-            confidence_scores: self.calculate_confidence_scores(&output)?,
+            // The model has no entity recognition head
+            detected_entities: Vec::new(),
+            confidence_scores: Self::calculate_confidence_scores(&output),
         })
     }
 
-    fn extract_entities(&self, content: &str) -> Result<Vec<String>> {
-        // TODO: Implement NER model inference
-        // For now, return a simple implementation
-        Ok(vec!["person".to_string(), "organization".to_string()])
-    }
-
     fn calculate_confidence_scores(
-        &self,
         output: &tract_ndarray::ArrayView4<f32>,
-    ) -> Result<std::collections::HashMap<String, f32>> {
+    ) -> std::collections::HashMap<String, f32> {
         let mut scores = std::collections::HashMap::new();
         scores.insert("fact_check".to_string(), output[[0, 0, 0, 0]].clamp(0.0, 1.0));
         scores.insert("source_reliability".to_string(), output[[0, 0, 1, 0]].clamp(0.0, 1.0));
         scores.insert("content_quality".to_string(), output[[0, 0, 2, 0]].clamp(0.0, 1.0));
         scores.insert("bias_detection".to_string(), output[[0, 0, 3, 0]].clamp(0.0, 1.0));
-        Ok(scores)
+        scores
+    }
+}
+
+#[async_trait]
+impl ContentAnalyzer for AIModel {
+    /// Inference is CPU-bound, so it runs on the blocking pool rather than
+    /// stalling the async workers.
+    async fn analyze_content(&self, content: &str) -> Result<AIAnalysis> {
+        let model = Arc::clone(&self.model);
+        let tokenizer = Arc::clone(&self.tokenizer);
+        let content = content.to_string();
+
+        let start = Instant::now();
+        let analysis =
+            tokio::task::spawn_blocking(move || Self::infer(&model, &tokenizer, &content))
+                .await
+                .map_err(|e| AppError::Internal(format!("Inference task failed: {}", e)))
+                .and_then(|result| result);
+        metrics::record_inference(start.elapsed(), analysis.is_ok());
+        analysis
+    }
+
+    /// A model that loads can still fail at inference, e.g. on an input
+    /// shape it was not exported for, so readiness waits for one inference
+    /// on a sample text to succeed. Once it has, the model stays ready.
+    async fn check_ready(&self) -> Result<()> {
+        self.warmed_up
+            .get_or_try_init(|| async {
+                let model = Arc::clone(&self.model);
+                let tokenizer = Arc::clone(&self.tokenizer);
+                tokio::task::spawn_blocking(move || {
+                    Self::infer(&model, &tokenizer, WARM_UP_TEXT).map(drop)
+                })
+                .await
+                .map_err(|e| AppError::Internal(format!("Warm-up task failed: {}", e)))?
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// The four scores are read from `[0, 0, 0..4, 0]`, so every axis must
+/// reach those indexes.
+fn check_output_shape(shape: &[usize]) -> Result<()> {
+    if shape.len() != 4 || shape[0] < 1 || shape[1] < 1 || shape[2] < 4 || shape[3] < 1 {
+        return Err(AppError::Internal(format!(
+            "Unexpected model output shape: {:?}",
+            shape
+        )));
+    }
+    Ok(())
+}
+
+/// Sample input for the readiness check's inference.
+const WARM_UP_TEXT: &str = "The city council approved the budget on Tuesday.";
+
+/// Returns fixed scores; lets the verification pipeline be tested without a
+/// model file.
+#[cfg(test)]
+pub struct StaticAnalyzer;
+
+#[cfg(test)]
+#[async_trait]
+impl ContentAnalyzer for StaticAnalyzer {
+    async fn analyze_content(&self, _content: &str) -> Result<AIAnalysis> {
+        Ok(AIAnalysis {
+            fact_check_score: 0.85,
+            source_reliability: 0.9,
+            content_quality: 0.8,
+            bias_detection: 0.1,
+            detected_entities: vec!["person".to_string(), "organization".to_string()],
+            confidence_scores: std::collections::HashMap::new(),
+        })
     }
 }
 
@@ -105,8 +197,14 @@ mod tests {
     use super::*;
 
     #[tokio::test]
+    #[ignore = "requires model files"]
     async fn test_ai_model() {
-        let model = AIModel::new("models/news_verification.onnx").unwrap();
+        let model = AIModel::new(
+            "models/news_verification.onnx",
+            "models/news_verification.tokenizer.json",
+        )
+        .unwrap();
+        model.check_ready().await.unwrap();
         let content = "This is a test article about a new technology breakthrough.";
         let analysis = model.analyze_content(content).await.unwrap();
 
@@ -115,4 +213,25 @@ mod tests {
         assert!(analysis.content_quality >= 0.0 && analysis.content_quality <= 1.0);
         assert!(analysis.bias_detection >= 0.0 && analysis.bias_detection <= 1.0);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_check_output_shape() {
+        assert!(check_output_shape(&[1, 1, 4, 1]).is_ok());
+        assert!(check_output_shape(&[1, 1, 8, 2]).is_ok());
+        for shape in [[0, 1, 4, 1], [1, 0, 4, 1], [1, 1, 3, 1], [1, 1, 4, 0]] {
+            assert!(
+                matches!(check_output_shape(&shape), Err(AppError::Internal(_))),
+                "accepted {:?}",
+                shape
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_model_is_an_error() {
+        assert!(matches!(
+            AIModel::new("models/does_not_exist.onnx", "models/does_not_exist.json"),
+            Err(AppError::Internal(_))
+        ));
+    }
+}
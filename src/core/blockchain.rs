@@ -1,13 +1,19 @@
-use crate::error::{AppError, Result};
+use crate::{
+    error::{AppError, Result},
+    metrics,
+};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{str::FromStr, time::Instant};
+use tokio::sync::OnceCell;
 use web3::{
     contract::{Contract, Options},
-    types::{Address, H256, U256},
+    signing::{Key, SecretKey, SecretKeyRef},
+    types::{Address, BlockId, H256, U256, U64},
     Web3,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockchainProof {
     pub transaction_hash: String,
     pub block_number: u64,
@@ -15,14 +21,46 @@ pub struct BlockchainProof {
     pub smart_contract_state: String,
 }
 
+/// Anchors verification proofs for articles, identified by a 32-byte hash of
+/// the verifying tenant and the article's content.
+#[async_trait]
+pub trait ProvenanceLedger: Send + Sync {
+    /// Records `verification_data` for the article, replacing any earlier
+    /// record for the same hash.
+    async fn create_verification_proof(
+        &self,
+        article_hash: [u8; 32],
+        verification_data: &str,
+    ) -> Result<BlockchainProof>;
+
+    async fn verify_proof(&self, article_hash: [u8; 32]) -> Result<bool>;
+
+    /// Used by the readiness probe; should be cheap and send no transaction.
+    async fn check_ready(&self) -> Result<()>;
+}
+
+/// Client for the `NewsVerification` contract.
+///
+/// Transactions are signed locally when a private key is supplied; otherwise
+/// the node's first unlocked account is used, which suits dev nodes such as
+/// anvil.
 pub struct BlockchainClient {
     web3: Web3<web3::transports::Http>,
     contract: Contract<web3::transports::Http>,
-    contract_address: Address,
+    signer: Option<SecretKey>,
+    chain_id: u64,
+    chain_checked: OnceCell<()>,
+    confirmations: usize,
 }
 
 impl BlockchainClient {
-    pub fn new(network_url: &str, contract_address: &str) -> Result<Self> {
+    pub fn new(
+        network_url: &str,
+        contract_address: &str,
+        private_key: Option<&str>,
+        chain_id: u64,
+        confirmations: usize,
+    ) -> Result<Self> {
         let transport = web3::transports::Http::new(network_url)
             .map_err(|e| AppError::Internal(format!("Failed to create Web3 transport: {}", e)))?;
         let web3 = Web3::new(transport);
@@ -30,6 +68,11 @@ impl BlockchainClient {
         let contract_address = Address::from_str(contract_address)
             .map_err(|e| AppError::Internal(format!("Invalid contract address: {}", e)))?;
 
+        let signer = private_key
+            .map(|key| SecretKey::from_str(key.trim_start_matches("0x")))
+            .transpose()
+            .map_err(|e| AppError::Internal(format!("Invalid signer private key: {}", e)))?;
+
         // Load the contract ABI
         let contract_abi = include_bytes!("../../contracts/NewsVerification.json");
         let contract = Contract::from_json(
@@ -41,38 +84,142 @@ impl BlockchainClient {
         Ok(Self {
             web3,
             contract,
-            contract_address,
+            signer,
+            chain_id,
+            chain_checked: OnceCell::new(),
+            confirmations,
         })
     }
 
-    pub async fn create_verification_proof(
+    /// Fails unless the node is on the configured chain. Checked on first use
+    /// rather than at startup so the API can come up while the node is down.
+    async fn ensure_chain(&self) -> Result<()> {
+        self.chain_checked
+            .get_or_try_init(|| async {
+                let chain_id =
+                    self.web3.eth().chain_id().await.map_err(|e| {
+                        AppError::Internal(format!("Failed to get chain id: {}", e))
+                    })?;
+                if chain_id != U256::from(self.chain_id) {
+                    return Err(AppError::Internal(format!(
+                        "Node is on chain {}, expected {}",
+                        chain_id, self.chain_id
+                    )));
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn sender(&self) -> Result<Address> {
+        if let Some(key) = &self.signer {
+            return Ok(SecretKeyRef::new(key).address());
+        }
+
+        self.web3
+            .eth()
+            .accounts()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to list node accounts: {}", e)))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                AppError::Internal("No signer key configured and node has no accounts".to_string())
+            })
+    }
+
+    async fn send(
+        &self,
+        function: &str,
+        params: (H256, String),
+    ) -> Result<web3::types::TransactionReceipt> {
+        self.ensure_chain().await?;
+        let sender = self.sender().await?;
+
+        // Storage cost grows with the payload, so estimate rather than rely
+        // on the 100k default, with headroom for state changes in between.
+        let gas = self
+            .contract
+            .estimate_gas(function, params.clone(), sender, Options::default())
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to estimate gas: {}", e)))?;
+        let options = Options::with(|options| {
+            options.gas = Some(gas * U256::from(12) / U256::from(10));
+        });
+
+        let start = Instant::now();
+        let receipt = match &self.signer {
+            Some(key) => {
+                self.contract
+                    .signed_call_with_confirmations(
+                        function,
+                        params,
+                        options,
+                        self.confirmations,
+                        SecretKeyRef::new(key),
+                    )
+                    .await
+            }
+            None => {
+                self.contract
+                    .call_with_confirmations(function, params, sender, options, self.confirmations)
+                    .await
+            }
+        }
+        .map_err(|e| {
+            metrics::record_blockchain_transaction(function, "failed", start.elapsed());
+            AppError::Internal(format!("Failed to create verification proof: {}", e))
+        })?;
+
+        if receipt.status == Some(U64::zero()) {
+            metrics::record_blockchain_transaction(function, "reverted", start.elapsed());
+            return Err(AppError::Internal(format!(
+                "Verification transaction 0x{:x} reverted",
+                receipt.transaction_hash
+            )));
+        }
+
+        metrics::record_blockchain_transaction(function, "confirmed", start.elapsed());
+        Ok(receipt)
+    }
+}
+
+#[async_trait]
+impl ProvenanceLedger for BlockchainClient {
+    async fn create_verification_proof(
         &self,
-        article_hash: &str,
+        article_hash: [u8; 32],
         verification_data: &str,
     ) -> Result<BlockchainProof> {
-        // Convert article hash to bytes32
-        let article_hash_bytes = H256::from_str(article_hash)
-            .map_err(|e| AppError::Internal(format!("Invalid article hash: {}", e)))?;
-
-        // Call the smart contract to create verification proof
-        let result = self.contract.call(
-            "createVerificationProof",
-            (article_hash_bytes, verification_data),
-            None,
-            Options::default(),
-            None,
-        ).await.map_err(|e| AppError::Internal(format!("Failed to create verification proof: {}", e)))?;
+        let article_hash_bytes = H256::from(article_hash);
 
-        // Get transaction receipt
-        let receipt = self.web3.eth()
-            .transaction_receipt(result)
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to get transaction receipt: {}", e)))?
-            .ok_or_else(|| AppError::Internal("Transaction receipt not found".to_string()))?;
+        let params = (article_hash_bytes, verification_data.to_string());
+
+        // The contract refuses to create a second record for the same hash
+        let receipt = if self.verify_proof(article_hash).await? {
+            self.send("updateVerification", params).await?
+        } else {
+            match self.send("createVerificationProof", params.clone()).await {
+                Ok(receipt) => receipt,
+                Err(e) => {
+                    // A concurrent verification of the same article may have
+                    // created the record between the check and the send, so
+                    // retry once as an update if it now exists.
+                    if !self.verify_proof(article_hash).await? {
+                        return Err(e);
+                    }
+                    self.send("updateVerification", params).await?
+                }
+            }
+        };
+        let block_number = receipt
+            .block_number
+            .ok_or_else(|| AppError::Internal("Transaction receipt has no block".to_string()))?;
 
         // Get block information
         let block = self.web3.eth()
-            .block(receipt.block_number.unwrap())
+            .block(BlockId::Number(block_number.into()))
             .await
             .map_err(|e| AppError::Internal(format!("Failed to get block: {}", e)))?
             .ok_or_else(|| AppError::Internal("Block not found".to_string()))?;
@@ -88,20 +235,18 @@ impl BlockchainClient {
 
         Ok(BlockchainProof {
             transaction_hash: format!("0x{:x}", receipt.transaction_hash),
-            block_number: receipt.block_number.unwrap().as_u64(),
+            block_number: block_number.as_u64(),
             timestamp: chrono::DateTime::from_timestamp(block.timestamp.as_u64() as i64, 0)
-                .unwrap_or_else(|| chrono::Utc::now()),
+                .unwrap_or_else(chrono::Utc::now),
             smart_contract_state: state,
         })
     }
 
-    pub async fn verify_proof(&self, article_hash: &str) -> Result<bool> {
-        let article_hash_bytes = H256::from_str(article_hash)
-            .map_err(|e| AppError::Internal(format!("Invalid article hash: {}", e)))?;
-
+    async fn verify_proof(&self, article_hash: [u8; 32]) -> Result<bool> {
+        self.ensure_chain().await?;
         let is_verified: bool = self.contract.query(
             "verifyProof",
-            (article_hash_bytes,),
+            (H256::from(article_hash),),
             None,
             Options::default(),
             None,
@@ -109,27 +254,90 @@ impl BlockchainClient {
 
         Ok(is_verified)
     }
+
+    async fn check_ready(&self) -> Result<()> {
+        self.ensure_chain().await?;
+        self.web3
+            .eth()
+            .block_number()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to get block number: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Returns a fixed proof without touching a chain; lets the verification
+/// pipeline be tested without a node.
+#[cfg(test)]
+pub struct StaticLedger;
+
+#[cfg(test)]
+#[async_trait]
+impl ProvenanceLedger for StaticLedger {
+    async fn create_verification_proof(
+        &self,
+        _article_hash: [u8; 32],
+        verification_data: &str,
+    ) -> Result<BlockchainProof> {
+        Ok(BlockchainProof {
+            transaction_hash: "0x123...".to_string(),
+            block_number: 12345,
+            timestamp: chrono::Utc::now(),
+            smart_contract_state: verification_data.to_string(),
+        })
+    }
+
+    async fn verify_proof(&self, _article_hash: [u8; 32]) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn check_ready(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Runs against a local dev node, e.g. `anvil` with the contract deployed
+    /// at the address below by its first default account.
     #[tokio::test]
+    #[ignore = "requires anvil"]
     async fn test_blockchain_client() {
         let client = BlockchainClient::new(
             "http://localhost:8545",
             "0x1234567890123456789012345678901234567890",
+            None,
+            31337,
+            0,
         ).unwrap();
 
-        let article_hash = "0x1234567890123456789012345678901234567890123456789012345678901234";
+        let article_hash = [0x12; 32];
         let verification_data = "{\"score\": 0.95, \"timestamp\": 1234567890}";
 
         let proof = client.create_verification_proof(article_hash, verification_data).await.unwrap();
         assert!(!proof.transaction_hash.is_empty());
         assert!(proof.block_number > 0);
+        assert_eq!(proof.smart_contract_state, verification_data);
 
         let is_verified = client.verify_proof(article_hash).await.unwrap();
         assert!(is_verified);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_rejects_invalid_configuration() {
+        assert!(
+            BlockchainClient::new("http://localhost:8545", "not-an-address", None, 31337, 0)
+                .is_err()
+        );
+        assert!(BlockchainClient::new(
+            "http://localhost:8545",
+            "0x1234567890123456789012345678901234567890",
+            Some("0xnot-a-key"),
+            31337,
+            0,
+        )
+        .is_err());
+    }
+}
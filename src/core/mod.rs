@@ -1 +1,3 @@
-pub mod news_verification; 
\ No newline at end of file
+pub mod ai_model;
+pub mod blockchain;
+pub mod news_verification;
\ No newline at end of file
@@ -1,6 +1,14 @@
-use crate::error::Result;
+use crate::core::{
+    ai_model::{AIAnalysis, ContentAnalyzer},
+    blockchain::{BlockchainProof, ProvenanceLedger},
+};
+use crate::db;
+use crate::error::{AppError, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use sqlx::{types::Json, PgPool};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,7 +27,8 @@ pub struct NewsArticle {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
 pub enum VerificationStatus {
     Pending,
     Verified,
@@ -27,67 +36,244 @@ pub enum VerificationStatus {
     UnderReview,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationResult {
     pub article_id: Uuid,
     pub credibility_score: f32,
+    /// Decided from `credibility_score` when the article was verified.
+    pub verification_status: VerificationStatus,
     pub ai_analysis: AIAnalysis,
     pub blockchain_proof: BlockchainProof,
     pub verification_timestamp: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AIAnalysis {
-    pub fact_check_score: f32,
-    pub source_reliability: f32,
-    pub content_quality: f32,
-    pub bias_detection: f32,
-    pub detected_entities: Vec<String>,
-    pub confidence_scores: std::collections::HashMap<String, f32>,
+/// Credibility score bands that decide a verified article's status.
+#[derive(Debug, Clone, Copy)]
+pub struct CredibilityThresholds {
+    /// Scores at or above this are `Verified`.
+    pub verified: f32,
+    /// Scores below this are `Rejected`; anything in between goes to review.
+    pub rejected: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockchainProof {
-    pub transaction_hash: String,
-    pub block_number: u64,
-    pub timestamp: DateTime<Utc>,
-    pub smart_contract_state: String,
+impl CredibilityThresholds {
+    pub fn status_for(&self, credibility_score: f32) -> VerificationStatus {
+        if credibility_score >= self.verified {
+            VerificationStatus::Verified
+        } else if credibility_score < self.rejected {
+            VerificationStatus::Rejected
+        } else {
+            VerificationStatus::UnderReview
+        }
+    }
+}
+
+impl Default for CredibilityThresholds {
+    fn default() -> Self {
+        Self {
+            verified: 0.7,
+            rejected: 0.4,
+        }
+    }
+}
+
+/// Persistence for verification results, keyed by tenant and article id.
+#[async_trait]
+pub trait VerificationStore: Send + Sync {
+    /// Re-verifying an article replaces the tenant's previous result.
+    async fn save(&self, tenant_id: Uuid, result: &VerificationResult) -> Result<()>;
+    async fn find(&self, tenant_id: Uuid, article_id: Uuid) -> Result<Option<VerificationResult>>;
+}
+
+pub struct PgVerificationStore {
+    pool: PgPool,
+}
+
+impl PgVerificationStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl VerificationStore for PgVerificationStore {
+    async fn save(&self, tenant_id: Uuid, result: &VerificationResult) -> Result<()> {
+        let mut tx = db::begin_tenant(&self.pool, tenant_id).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO news_verifications (
+                tenant_id, article_id, credibility_score, verification_status, ai_analysis,
+                transaction_hash, block_number, block_timestamp, smart_contract_state,
+                verification_timestamp
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (tenant_id, article_id) DO UPDATE SET
+                credibility_score = EXCLUDED.credibility_score,
+                verification_status = EXCLUDED.verification_status,
+                ai_analysis = EXCLUDED.ai_analysis,
+                transaction_hash = EXCLUDED.transaction_hash,
+                block_number = EXCLUDED.block_number,
+                block_timestamp = EXCLUDED.block_timestamp,
+                smart_contract_state = EXCLUDED.smart_contract_state,
+                verification_timestamp = EXCLUDED.verification_timestamp,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(result.article_id)
+        .bind(result.credibility_score)
+        .bind(result.verification_status)
+        .bind(Json(&result.ai_analysis))
+        .bind(&result.blockchain_proof.transaction_hash)
+        .bind(result.blockchain_proof.block_number as i64)
+        .bind(result.blockchain_proof.timestamp)
+        .bind(&result.blockchain_proof.smart_contract_state)
+        .bind(result.verification_timestamp)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn find(&self, tenant_id: Uuid, article_id: Uuid) -> Result<Option<VerificationResult>> {
+        let mut tx = db::begin_tenant(&self.pool, tenant_id).await?;
+        let record = sqlx::query_as::<_, VerificationRecord>(
+            r#"
+            SELECT article_id, credibility_score, verification_status, ai_analysis,
+                   transaction_hash, block_number, block_timestamp, smart_contract_state,
+                   verification_timestamp
+            FROM news_verifications
+            WHERE tenant_id = $1 AND article_id = $2
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(article_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(record.map(Into::into))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct VerificationRecord {
+    article_id: Uuid,
+    credibility_score: f32,
+    verification_status: VerificationStatus,
+    ai_analysis: Json<AIAnalysis>,
+    transaction_hash: String,
+    block_number: i64,
+    block_timestamp: DateTime<Utc>,
+    smart_contract_state: String,
+    verification_timestamp: DateTime<Utc>,
+}
+
+impl From<VerificationRecord> for VerificationResult {
+    fn from(record: VerificationRecord) -> Self {
+        Self {
+            article_id: record.article_id,
+            credibility_score: record.credibility_score,
+            verification_status: record.verification_status,
+            ai_analysis: record.ai_analysis.0,
+            blockchain_proof: BlockchainProof {
+                transaction_hash: record.transaction_hash,
+                block_number: record.block_number as u64,
+                timestamp: record.block_timestamp,
+                smart_contract_state: record.smart_contract_state,
+            },
+            verification_timestamp: record.verification_timestamp,
+        }
+    }
 }
 
 pub struct NewsVerificationService {
-    ai_model: AIModel,
-    blockchain_client: BlockchainClient,
+    store: Box<dyn VerificationStore>,
+    analyzer: Box<dyn ContentAnalyzer>,
+    ledger: Box<dyn ProvenanceLedger>,
+    thresholds: CredibilityThresholds,
 }
 
 impl NewsVerificationService {
-    pub fn new(ai_model: AIModel, blockchain_client: BlockchainClient) -> Self {
+    pub fn new(
+        store: impl VerificationStore + 'static,
+        analyzer: impl ContentAnalyzer + 'static,
+        ledger: impl ProvenanceLedger + 'static,
+        thresholds: CredibilityThresholds,
+    ) -> Self {
         Self {
-            ai_model,
-            blockchain_client,
+            store: Box::new(store),
+            analyzer: Box::new(analyzer),
+            ledger: Box::new(ledger),
+            thresholds,
         }
     }
 
-    pub async fn verify_article(&self, article: &NewsArticle) -> Result<VerificationResult> {
+    pub async fn verify_article(
+        &self,
+        tenant_id: Uuid,
+        article: &NewsArticle,
+    ) -> Result<VerificationResult> {
         // 1. Perform AI analysis
-        let ai_analysis = self.ai_model.analyze_content(&article.content).await?;
+        let ai_analysis = self.analyzer.analyze_content(&article.content).await?;
 
         // 2. Calculate credibility score
         let credibility_score = self.calculate_credibility_score(&ai_analysis);
+        let verification_status = self.thresholds.status_for(credibility_score);
 
-        // 3. Create blockchain proof
+        // 3. Anchor the proof on chain under the tenant's hash of the article
+        let verification_data = verification_data(article, credibility_score, &ai_analysis);
         let blockchain_proof = self
-            .blockchain_client
-            .create_verification_proof(article, &ai_analysis)
+            .ledger
+            .create_verification_proof(article_hash(tenant_id, article), &verification_data)
             .await?;
 
-        // 4. Return verification result
-        Ok(VerificationResult {
+        // 4. Persist and return verification result
+        let result = VerificationResult {
             article_id: article.id,
             credibility_score,
+            verification_status,
             ai_analysis,
             blockchain_proof,
             verification_timestamp: Utc::now(),
-        })
+        };
+        self.store.save(tenant_id, &result).await?;
+
+        Ok(result)
+    }
+
+    /// Articles verified only by other tenants are reported as unverified.
+    pub async fn get_verification(
+        &self,
+        tenant_id: Uuid,
+        article_id: Uuid,
+    ) -> Result<VerificationResult> {
+        self.store
+            .find(tenant_id, article_id)
+            .await?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Article {} has not been verified", article_id))
+            })
+    }
+
+    pub async fn get_blockchain_proof(
+        &self,
+        tenant_id: Uuid,
+        article_id: Uuid,
+    ) -> Result<BlockchainProof> {
+        Ok(self
+            .get_verification(tenant_id, article_id)
+            .await?
+            .blockchain_proof)
+    }
+
+    pub async fn check_analyzer(&self) -> Result<()> {
+        self.analyzer.check_ready().await
+    }
+
+    pub async fn check_ledger(&self) -> Result<()> {
+        self.ledger.check_ready().await
     }
 
     fn calculate_credibility_score(&self, analysis: &AIAnalysis) -> f32 {
@@ -103,75 +289,85 @@ impl NewsVerificationService {
     }
 }
 
-// AI Model for content analysis
-pub struct AIModel {
-    model_path: String,
+/// Fields are declared in alphabetical order so the serialized form is
+/// sorted-key JSON, which anyone can reproduce to check a hash.
+#[derive(Serialize)]
+struct CanonicalArticle<'a> {
+    author: &'a str,
+    content: &'a str,
+    published_at: i64,
+    source_url: &'a str,
+    tenant_id: Uuid,
+    title: &'a str,
 }
 
-impl AIModel {
-    pub fn new(model_path: String) -> Self {
-        Self { model_path }
-    }
+/// Keccak-256 over the verifying tenant and the fields that make up an
+/// article's published content. Ids, status and bookkeeping timestamps are
+/// left out so the same article always maps to the same on-chain record,
+/// while tenants verifying the same article never overwrite each other.
+pub fn article_hash(tenant_id: Uuid, article: &NewsArticle) -> [u8; 32] {
+    let canonical = CanonicalArticle {
+        author: &article.author,
+        content: &article.content,
+        published_at: article.published_at.timestamp(),
+        source_url: &article.source_url,
+        tenant_id,
+        title: &article.title,
+    };
 
-    pub async fn analyze_content(&self, content: &str) -> Result<AIAnalysis> {
-        // TODO: Implement actual AI model inference
-        // This is a placeholder implementation
-        Ok(AIAnalysis {
-            fact_check_score: 0.85,
-            source_reliability: 0.9,
-            content_quality: 0.8,
-            bias_detection: 0.1,
-            detected_entities: vec!["person".to_string(), "organization".to_string()],
-            confidence_scores: std::collections::HashMap::new(),
-        })
-    }
+    // Serializing a struct of strings and integers cannot fail.
+    let bytes = serde_json::to_vec(&canonical).unwrap_or_default();
+    Keccak256::digest(bytes).into()
 }
 
-// Blockchain client for provenance tracking
-pub struct BlockchainClient {
-    network_url: String,
-    contract_address: String,
+/// Compact summary stored on chain alongside the article hash.
+fn verification_data(article: &NewsArticle, credibility_score: f32, analysis: &AIAnalysis) -> String {
+    serde_json::json!({
+        "article_id": article.id,
+        "credibility_score": credibility_score,
+        "fact_check_score": analysis.fact_check_score,
+        "source_reliability": analysis.source_reliability,
+        "content_quality": analysis.content_quality,
+        "bias_detection": analysis.bias_detection,
+    })
+    .to_string()
 }
 
-impl BlockchainClient {
-    pub fn new(network_url: String, contract_address: String) -> Self {
-        Self {
-            network_url,
-            contract_address,
-        }
+/// In-memory `VerificationStore` for tests that should not need Postgres.
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryVerificationStore {
+    results: std::sync::Mutex<std::collections::HashMap<(Uuid, Uuid), VerificationResult>>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl VerificationStore for InMemoryVerificationStore {
+    async fn save(&self, tenant_id: Uuid, result: &VerificationResult) -> Result<()> {
+        self.results
+            .lock()
+            .unwrap()
+            .insert((tenant_id, result.article_id), result.clone());
+        Ok(())
     }
 
-    pub async fn create_verification_proof(
-        &self,
-        article: &NewsArticle,
-        analysis: &AIAnalysis,
-    ) -> Result<BlockchainProof> {
-        // TODO: Implement actual blockchain interaction
-        // This is a placeholder implementation
-        Ok(BlockchainProof {
-            transaction_hash: "0x123...".to_string(),
-            block_number: 12345,
-            timestamp: Utc::now(),
-            smart_contract_state: "verified".to_string(),
-        })
+    async fn find(&self, tenant_id: Uuid, article_id: Uuid) -> Result<Option<VerificationResult>> {
+        Ok(self
+            .results
+            .lock()
+            .unwrap()
+            .get(&(tenant_id, article_id))
+            .cloned())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::{ai_model::StaticAnalyzer, blockchain::StaticLedger};
 
-    #[tokio::test]
-    async fn test_news_verification() {
-        let ai_model = AIModel::new("models/news_verification".to_string());
-        let blockchain_client = BlockchainClient::new(
-            "https://mainnet.infura.io/v3/your-project-id".to_string(),
-            "0x123...".to_string(),
-        );
-
-        let service = NewsVerificationService::new(ai_model, blockchain_client);
-
-        let article = NewsArticle {
+    fn test_article() -> NewsArticle {
+        NewsArticle {
             id: Uuid::new_v4(),
             title: "Test Article".to_string(),
             content: "This is a test article content.".to_string(),
@@ -184,9 +380,113 @@ mod tests {
             smart_contract_address: "".to_string(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
-        };
+        }
+    }
+
+    #[tokio::test]
+    async fn test_news_verification() {
+        let service = NewsVerificationService::new(
+            InMemoryVerificationStore::default(),
+            StaticAnalyzer,
+            StaticLedger,
+            CredibilityThresholds::default(),
+        );
+
+        let article = test_article();
+        let tenant_id = Uuid::new_v4();
 
-        let result = service.verify_article(&article).await.unwrap();
+        let result = service.verify_article(tenant_id, &article).await.unwrap();
         assert!(result.credibility_score >= 0.0 && result.credibility_score <= 1.0);
+        assert_eq!(result.verification_status, VerificationStatus::Verified);
+
+        let stored = service
+            .get_verification(tenant_id, article.id)
+            .await
+            .unwrap();
+        assert_eq!(stored.article_id, article.id);
+        assert_eq!(
+            stored.blockchain_proof.transaction_hash,
+            result.blockchain_proof.transaction_hash
+        );
+
+        // Another tenant has not verified the article
+        assert!(matches!(
+            service.get_verification(Uuid::new_v4(), article.id).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_credibility_thresholds() {
+        let thresholds = CredibilityThresholds {
+            verified: 0.7,
+            rejected: 0.4,
+        };
+        assert_eq!(thresholds.status_for(0.7), VerificationStatus::Verified);
+        assert_eq!(thresholds.status_for(0.5), VerificationStatus::UnderReview);
+        assert_eq!(thresholds.status_for(0.4), VerificationStatus::UnderReview);
+        assert_eq!(thresholds.status_for(0.39), VerificationStatus::Rejected);
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_unverified_article_is_not_found() {
+        let service = NewsVerificationService::new(
+            InMemoryVerificationStore::default(),
+            StaticAnalyzer,
+            StaticLedger,
+            CredibilityThresholds::default(),
+        );
+
+        assert!(matches!(
+            service
+                .get_verification(Uuid::new_v4(), Uuid::new_v4())
+                .await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_article_hash_covers_published_content_only() {
+        let tenant_id = Uuid::new_v4();
+        let article = test_article();
+        let mut copy = test_article();
+        copy.published_at = article.published_at;
+        assert_eq!(
+            article_hash(tenant_id, &article),
+            article_hash(tenant_id, &copy)
+        );
+
+        copy.content.push('!');
+        assert_ne!(
+            article_hash(tenant_id, &article),
+            article_hash(tenant_id, &copy)
+        );
+    }
+
+    #[test]
+    fn test_article_hash_is_scoped_to_tenant() {
+        let article = test_article();
+        assert_ne!(
+            article_hash(Uuid::new_v4(), &article),
+            article_hash(Uuid::new_v4(), &article)
+        );
+    }
+
+    #[test]
+    fn test_article_hash_is_keccak256() {
+        let tenant_id = Uuid::new_v4();
+        let article = test_article();
+        let canonical = serde_json::json!({
+            "tenant_id": tenant_id,
+            "title": article.title,
+            "content": article.content,
+            "source_url": article.source_url,
+            "author": article.author,
+            "published_at": article.published_at.timestamp(),
+        });
+        let expected: [u8; 32] =
+            Keccak256::digest(serde_json::to_vec(&canonical).unwrap()).into();
+
+        assert_eq!(article_hash(tenant_id, &article), expected);
+    }
+}
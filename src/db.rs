@@ -1,13 +1,17 @@
 use crate::config::{DatabaseConfig, KafkaConfig, RedisConfig};
-use rdkafka::config::ClientConfig;
-use sqlx::postgres::PgPoolOptions;
+use rdkafka::{
+    config::ClientConfig,
+    consumer::{Consumer, StreamConsumer},
+};
+use sqlx::{postgres::PgPoolOptions, PgPool, Postgres, Transaction};
 use std::time::Duration;
+use uuid::Uuid;
 
 pub async fn init_pool(config: &DatabaseConfig) -> Result<sqlx::PgPool, sqlx::Error> {
     PgPoolOptions::new()
         .max_connections(config.max_connections)
         .min_connections(config.min_connections)
-        .connect_timeout(Duration::from_secs(config.connect_timeout))
+        .acquire_timeout(Duration::from_secs(config.connect_timeout))
         .connect(&config.url)
         .await
 }
@@ -23,11 +27,65 @@ pub fn init_kafka_producer(config: &KafkaConfig) -> Result<rdkafka::producer::Fu
         .set("client.id", &config.client_id)
         .set("group.id", &config.group_id)
         .set("message.timeout.ms", "5000")
+        .set("acks", "all")
+        .set("enable.idempotence", "true")
         .create()?;
 
     Ok(producer)
 }
 
+pub fn init_kafka_consumer(config: &KafkaConfig) -> Result<StreamConsumer, rdkafka::error::KafkaError> {
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", config.brokers.join(","))
+        .set("client.id", &config.client_id)
+        .set("group.id", &config.group_id)
+        .set("enable.auto.commit", "false")
+        .set("auto.offset.reset", "earliest")
+        .create()?;
+
+    let topics: Vec<&str> = config.consumer_topics.iter().map(String::as_str).collect();
+    consumer.subscribe(&topics)?;
+
+    Ok(consumer)
+}
+
+/// Starts a transaction scoped to `tenant_id`. Row-level security on tenant
+/// tables only admits rows of the tenant set here, and queries outside such
+/// a transaction fail.
+pub async fn begin_tenant(
+    pool: &PgPool,
+    tenant_id: Uuid,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    set_tenant(&mut tx, tenant_id).await?;
+    Ok(tx)
+}
+
+/// Starts a transaction that may read `users` and `api_keys` of every
+/// tenant, for resolving credentials before the tenant is known. Writes
+/// still need `set_tenant`.
+pub async fn begin_credential_lookup(
+    pool: &PgPool,
+) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT set_config('app.credential_lookup', 'on', true)")
+        .execute(&mut *tx)
+        .await?;
+    Ok(tx)
+}
+
+/// Switches the tenant of an open transaction; the setting ends with it.
+pub async fn set_tenant(
+    tx: &mut Transaction<'static, Postgres>,
+    tenant_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT set_config('app.tenant_id', $1, true)")
+        .bind(tenant_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
 pub async fn run_migrations(pool: &sqlx::PgPool) -> Result<(), sqlx::Error> {
     sqlx::migrate!("./migrations")
         .run(pool)
@@ -56,7 +114,6 @@ mod tests {
     async fn test_redis_connection() {
         let config = RedisConfig {
             url: "redis://localhost:6379".to_string(),
-            pool_size: 5,
         };
 
         let client = init_redis(&config).await;
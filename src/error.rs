@@ -26,9 +26,15 @@ pub enum AppError {
     #[error("Authentication error: {0}")]
     Auth(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Internal server error: {0}")]
     Internal(String),
 }
@@ -42,7 +48,9 @@ impl IntoResponse for AppError {
             AppError::Config(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             AppError::Validation(e) => (StatusCode::BAD_REQUEST, e),
             AppError::Auth(e) => (StatusCode::UNAUTHORIZED, e),
+            AppError::Forbidden(e) => (StatusCode::FORBIDDEN, e),
             AppError::NotFound(e) => (StatusCode::NOT_FOUND, e),
+            AppError::Conflict(e) => (StatusCode::CONFLICT, e),
             AppError::Internal(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
         };
 
@@ -76,4 +84,18 @@ mod tests {
         let response = error.into_response();
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[test]
+    fn test_forbidden_error() {
+        let error = AppError::Forbidden("Admins only".to_string());
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_conflict_error() {
+        let error = AppError::Conflict("Already undone".to_string());
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
 } 
\ No newline at end of file
@@ -3,19 +3,34 @@ mod config;
 mod core;
 mod db;
 mod error;
+mod metrics;
 mod models;
+mod server;
 mod services;
-mod utils;
 
 use axum::{
-    routing::{get, post},
+    extract::{DefaultBodyLimit, FromRef},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
-use std::net::SocketAddr;
+use metrics_exporter_prometheus::PrometheusHandle;
+use rdkafka::producer::Producer;
+use std::{sync::Arc, time::Duration};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::core::news_verification::{AIModel, BlockchainClient, NewsVerificationService};
+use crate::core::{
+    ai_model::AIModel,
+    blockchain::BlockchainClient,
+    news_verification::{CredibilityThresholds, NewsVerificationService, PgVerificationStore},
+};
+use crate::models::Role;
+use crate::services::{
+    analytics_cache::AnalyticsCache, auth::AuthService, duplicates::DuplicateScanner,
+    event_consumer::EventConsumer, event_publisher::EventPublisher,
+    identities::IdentityResolver, sessions::SessionStore, tasks::OverdueTaskNotifier,
+};
 
 #[tokio::main]
 async fn main() {
@@ -28,36 +43,133 @@ async fn main() {
         .init();
 
     // Load configuration
-    let config = config::Config::load().expect("Failed to load configuration");
+    let config = config::Config::load()
+        .unwrap_or_else(|e| panic!("Failed to load configuration: {}", e));
 
     // Initialize database connection
     let db_pool = db::init_pool(&config.database)
         .await
         .expect("Failed to initialize database pool");
 
+    // Apply pending schema migrations
+    db::run_migrations(&db_pool)
+        .await
+        .expect("Failed to run database migrations");
+
+    // Promote the configured user so a tenant without admins can be managed
+    if let Some(email) = &config.auth.bootstrap_admin_email {
+        match api::auth::bootstrap_admin(&db_pool, email)
+            .await
+            .expect("Failed to bootstrap admin")
+        {
+            Some(user) => {
+                tracing::info!(user_id = %user.id, tenant_id = %user.tenant_id, "bootstrap admin promoted")
+            }
+            None => tracing::warn!(email = %email, "bootstrap admin user not found"),
+        }
+    }
+
     // Initialize Redis connection
     let redis_client = db::init_redis(&config.redis)
         .await
         .expect("Failed to initialize Redis client");
+    let redis_connection = redis::aio::ConnectionManager::new(redis_client.clone())
+        .await
+        .expect("Failed to connect to Redis");
+    let analytics_cache = AnalyticsCache::new(redis_connection);
+    let sessions = SessionStore::new(redis_client.clone(), &config.auth);
 
     // Initialize Kafka producer
     let kafka_producer = db::init_kafka_producer(&config.kafka)
         .expect("Failed to initialize Kafka producer");
+    let event_publisher =
+        EventPublisher::new(kafka_producer.clone(), config.kafka.events_topic.clone());
+    let identity_resolver = IdentityResolver::new(&config.identity);
+
+    // Start the Kafka ingestion consumer when topics are configured
+    if !config.kafka.consumer_topics.is_empty() {
+        let kafka_consumer = db::init_kafka_consumer(&config.kafka)
+            .expect("Failed to initialize Kafka consumer");
+        let event_consumer = EventConsumer::new(
+            kafka_consumer,
+            kafka_producer.clone(),
+            db_pool.clone(),
+            analytics_cache.clone(),
+            identity_resolver.clone(),
+            &config.kafka,
+        );
+        tokio::spawn(event_consumer.run());
+    }
+
+    // Score contacts for likely duplicates in the background
+    let duplicate_scanner = DuplicateScanner::new(db_pool.clone(), &config.duplicates);
+    tokio::spawn(duplicate_scanner.run());
+
+    // Publish a notification for each task that passes its due date
+    let overdue_task_notifier = OverdueTaskNotifier::new(
+        db_pool.clone(),
+        kafka_producer.clone(),
+        config.kafka.task_notifications_topic.clone(),
+        &config.tasks,
+    );
+    tokio::spawn(overdue_task_notifier.run());
 
     // Initialize news verification service
-    let ai_model = AIModel::new("models/news_verification".to_string());
+    let ai_model = AIModel::new(&config.ai_model.model_path, &config.ai_model.tokenizer_path)
+        .expect("Failed to load AI model");
+    let signer_key = config
+        .blockchain
+        .signer_key()
+        .expect("Failed to read blockchain signer key");
     let blockchain_client = BlockchainClient::new(
-        "https://mainnet.infura.io/v3/your-project-id".to_string(),
-        "0x123...".to_string(),
-    );
-    let news_verification_service = NewsVerificationService::new(ai_model, blockchain_client);
+        &config.blockchain.rpc_url,
+        &config.blockchain.contract_address,
+        signer_key.as_deref(),
+        config.blockchain.chain_id,
+        config.blockchain.confirmations,
+    )
+    .expect("Failed to initialize blockchain client");
+    let news_verification_service = Arc::new(NewsVerificationService::new(
+        PgVerificationStore::new(db_pool.clone()),
+        ai_model,
+        blockchain_client,
+        CredibilityThresholds {
+            verified: config.ai_model.verified_threshold,
+            rejected: config.ai_model.rejected_threshold,
+        },
+    ));
 
-    // Build our application with routes
-    let app = Router::new()
-        .route("/health", get(health_check))
+    let state = AppState {
+        db: db_pool,
+        redis: redis_client,
+        kafka: kafka_producer.clone(),
+        event_publisher,
+        analytics_cache,
+        identity_resolver,
+        auth: AuthService::new(&config.auth),
+        sessions,
+        news_verification: news_verification_service,
+        metrics: metrics::handle(),
+    };
+
+    // Routes below require a valid bearer token and the role noted per group
+    let events = Router::new()
         .route("/api/v1/events", post(api::events::create_event))
+        .route(
+            "/api/v1/events/batch",
+            post(api::events::create_events_batch)
+                .layer(DefaultBodyLimit::max(api::events::MAX_BATCH_BODY_BYTES)),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            api::auth::require_role(Role::Ingest, req, next)
+        }));
+    let analytics = Router::new()
         .route("/api/v1/analytics", get(api::analytics::get_analytics))
-        // News verification routes
+        .route_layer(middleware::from_fn(|req, next| {
+            api::auth::require_role(Role::Analyst, req, next)
+        }));
+    // News verification routes
+    let news = Router::new()
         .route("/api/v1/news/verify", post(api::news::verify_article))
         .route(
             "/api/v1/news/status/:article_id",
@@ -67,30 +179,198 @@ async fn main() {
             "/api/v1/news/proof/:article_id",
             get(api::news::get_blockchain_proof),
         )
+        .route_layer(middleware::from_fn(|req, next| {
+            api::auth::require_role(Role::NewsReviewer, req, next)
+        }));
+    let crm = Router::new()
+        .route(
+            "/api/v1/accounts",
+            get(api::accounts::list_accounts).post(api::accounts::create_account),
+        )
+        .route(
+            "/api/v1/accounts/:account_id",
+            get(api::accounts::get_account)
+                .put(api::accounts::update_account)
+                .delete(api::accounts::delete_account),
+        )
+        .route(
+            "/api/v1/accounts/:account_id/contacts",
+            get(api::accounts::list_account_contacts),
+        )
+        .route(
+            "/api/v1/contacts",
+            get(api::contacts::list_contacts).post(api::contacts::create_contact),
+        )
+        .route(
+            "/api/v1/contacts/:contact_id",
+            get(api::contacts::get_contact)
+                .put(api::contacts::update_contact)
+                .delete(api::contacts::delete_contact),
+        )
+        .route(
+            "/api/v1/contacts/:contact_id/events",
+            get(api::contacts::list_contact_events),
+        )
+        .route(
+            "/api/v1/contacts/:contact_id/merge",
+            post(api::duplicates::merge_contacts),
+        )
+        .route(
+            "/api/v1/contact-duplicates",
+            get(api::duplicates::list_duplicates),
+        )
+        .route(
+            "/api/v1/contact-duplicates/:duplicate_id/dismiss",
+            post(api::duplicates::dismiss_duplicate),
+        )
+        .route(
+            "/api/v1/contact-merges",
+            get(api::duplicates::list_merges),
+        )
+        .route(
+            "/api/v1/contact-merges/:merge_id/undo",
+            post(api::duplicates::undo_merge),
+        )
+        .route(
+            "/api/v1/pipelines",
+            get(api::pipelines::list_pipelines).post(api::pipelines::create_pipeline),
+        )
+        .route(
+            "/api/v1/pipelines/:pipeline_id",
+            get(api::pipelines::get_pipeline)
+                .put(api::pipelines::update_pipeline)
+                .delete(api::pipelines::delete_pipeline),
+        )
+        .route(
+            "/api/v1/deals",
+            get(api::deals::list_deals).post(api::deals::create_deal),
+        )
+        .route(
+            "/api/v1/deals/:deal_id",
+            get(api::deals::get_deal)
+                .put(api::deals::update_deal)
+                .delete(api::deals::delete_deal),
+        )
+        .route("/api/v1/deals/:deal_id/stage", post(api::deals::move_deal))
+        .route(
+            "/api/v1/deals/:deal_id/history",
+            get(api::deals::list_deal_history),
+        )
+        .route(
+            "/api/v1/activities",
+            get(api::activities::list_activities).post(api::activities::create_activity),
+        )
+        .route(
+            "/api/v1/activities/:activity_id",
+            get(api::activities::get_activity)
+                .put(api::activities::update_activity)
+                .delete(api::activities::delete_activity),
+        )
+        .route(
+            "/api/v1/tasks",
+            get(api::tasks::list_tasks).post(api::tasks::create_task),
+        )
+        .route(
+            "/api/v1/tasks/upcoming",
+            get(api::tasks::list_upcoming_tasks),
+        )
+        .route(
+            "/api/v1/tasks/:task_id",
+            get(api::tasks::get_task)
+                .put(api::tasks::update_task)
+                .delete(api::tasks::delete_task),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            api::auth::require_role(Role::Sales, req, next)
+        }));
+    let admin = Router::new()
+        .route("/api/v1/users", post(api::users::create_user))
+        .route("/api/v1/users/:user_id/role", put(api::users::update_user_role))
+        .route(
+            "/api/v1/api-keys",
+            get(api::api_keys::list_api_keys).post(api::api_keys::create_api_key),
+        )
+        .route(
+            "/api/v1/api-keys/:api_key_id",
+            delete(api::api_keys::revoke_api_key),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            api::auth::require_role(Role::Admin, req, next)
+        }));
+    let protected = Router::new()
+        .merge(events)
+        .merge(analytics)
+        .merge(news)
+        .merge(crm)
+        .merge(admin)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            api::auth::require_auth,
+        ));
+
+    // Registration creates a tenant with the caller as admin, so it is only
+    // served where self-service sign-up is enabled
+    let registration = if config.auth.allow_public_registration {
+        Router::new().route("/api/v1/auth/register", post(api::auth::register))
+    } else {
+        Router::new()
+    };
+
+    // Build our application with routes
+    let app = Router::new()
+        .route("/health", get(health_check))
+        .route("/health/live", get(api::health::live))
+        .route("/health/ready", get(api::health::ready))
+        .merge(registration)
+        .route("/api/v1/auth/login", post(api::auth::login))
+        .route("/api/v1/auth/refresh", post(api::auth::refresh))
+        .route("/api/v1/auth/logout", post(api::auth::logout))
+        .merge(protected)
+        .layer(middleware::from_fn(metrics::track_http))
         .layer(TraceLayer::new_for_http())
-        .with_state(AppState {
-            db: db_pool,
-            redis: redis_client,
-            kafka: kafka_producer,
-            news_verification: news_verification_service,
-        });
-
-    // Run our app with hyper
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    tracing::info!("listening on {}", addr);
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+        .with_state(state.clone());
+
+    // Serve metrics on their own listener, away from the public port
+    let metrics_app = Router::new()
+        .route("/metrics", get(metrics::render))
+        .with_state(state);
+    tokio::spawn({
+        let config = config.server.metrics.clone();
+        async move {
+            if let Err(e) = server::serve_metrics(metrics_app, config).await {
+                tracing::error!(error = %e, "metrics server failed");
+            }
+        }
+    });
+
+    // Run our app until SIGTERM, letting in-flight requests finish
+    server::serve(app, &config.server)
         .await
-        .unwrap();
+        .expect("Server error");
+
+    // Deliver events still queued in the producer before exiting
+    if let Err(e) = kafka_producer.flush(KAFKA_FLUSH_TIMEOUT) {
+        tracing::error!(error = %e, "failed to flush Kafka producer on shutdown");
+    }
+    tracing::info!("shutdown complete");
 }
 
+/// Upper bound on how long shutdown waits for queued Kafka records.
+const KAFKA_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
 // Application state
-#[derive(Clone)]
+#[derive(Clone, FromRef)]
 struct AppState {
     db: sqlx::PgPool,
     redis: redis::Client,
     kafka: rdkafka::producer::FutureProducer,
-    news_verification: NewsVerificationService,
+    event_publisher: EventPublisher,
+    analytics_cache: AnalyticsCache,
+    identity_resolver: IdentityResolver,
+    auth: AuthService,
+    sessions: SessionStore,
+    news_verification: Arc<NewsVerificationService>,
+    metrics: PrometheusHandle,
 }
 
 // Health check endpoint
@@ -0,0 +1,173 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// Bucket bounds for every `*_seconds` histogram. Covers fast API calls as
+/// well as inference and on-chain confirmations, which take seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Installs the Prometheus recorder on first use and returns its handle.
+/// Later calls return the same handle, since a process has one recorder.
+pub fn handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+                .and_then(|builder| builder.install_recorder())
+                .expect("Failed to install Prometheus recorder")
+        })
+        .clone()
+}
+
+/// `GET /metrics` in the Prometheus text format. Pool gauges are sampled
+/// here so they are current at every scrape.
+pub async fn render(
+    State(handle): State<PrometheusHandle>,
+    State(pool): State<PgPool>,
+) -> impl IntoResponse {
+    record_db_pool(&pool);
+    handle.render()
+}
+
+/// Records count and latency per route template, so `/users/:id` is one
+/// series rather than one per id. Unmatched paths share a single label.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed();
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::increment_counter!("http_requests_total", &labels);
+    metrics::histogram!("http_request_duration_seconds", elapsed, &labels);
+
+    response
+}
+
+/// Labelled by the `source` of the API key the events came in with, and by
+/// how they arrived: `api`, `kafka`, or `crm` for events the CRM emits
+/// itself. Key sources are set by admins, which keeps the series bounded;
+/// everything else is counted as `other`, since those sources are chosen by
+/// callers.
+pub fn record_events_ingested(api_key_source: Option<&str>, channel: &'static str, count: usize) {
+    if count > 0 {
+        let labels = [
+            ("source", api_key_source.unwrap_or("other").to_string()),
+            ("channel", channel.to_string()),
+        ];
+        metrics::counter!("events_ingested_total", count as u64, &labels);
+    }
+}
+
+pub fn record_kafka_delivery_failure(topic: &str) {
+    metrics::increment_counter!("kafka_delivery_failures_total", "topic" => topic.to_string());
+}
+
+pub fn record_db_pool(pool: &PgPool) {
+    let size = pool.size() as f64;
+    let idle = pool.num_idle() as f64;
+    metrics::gauge!("db_pool_connections", size - idle, "state" => "in_use");
+    metrics::gauge!("db_pool_connections", idle, "state" => "idle");
+    metrics::gauge!(
+        "db_pool_max_connections",
+        pool.options().get_max_connections() as f64
+    );
+}
+
+pub fn record_inference(elapsed: Duration, succeeded: bool) {
+    metrics::histogram!(
+        "ai_inference_duration_seconds",
+        elapsed,
+        "outcome" => outcome(succeeded)
+    );
+}
+
+/// `status` is `confirmed`, `reverted` or `failed` (never mined).
+pub fn record_blockchain_transaction(function: &str, status: &'static str, elapsed: Duration) {
+    let labels = [
+        ("function", function.to_string()),
+        ("status", status.to_string()),
+    ];
+    metrics::increment_counter!("blockchain_transactions_total", &labels);
+    metrics::histogram!(
+        "blockchain_transaction_confirmation_seconds",
+        elapsed,
+        &labels
+    );
+}
+
+fn outcome(succeeded: bool) -> &'static str {
+    if succeeded {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_track_http_labels_by_route_template() {
+        let handle = handle();
+        let app = Router::new()
+            .route("/metrics-test/:id", get(|| async { StatusCode::ACCEPTED }))
+            .layer(middleware::from_fn(track_http));
+
+        for path in [
+            "/metrics-test/1",
+            "/metrics-test/2",
+            "/metrics-test-missing",
+        ] {
+            app.clone()
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+
+        let rendered = handle.render();
+        assert!(rendered.contains(
+            r#"http_requests_total{method="GET",route="/metrics-test/:id",status="202"} 2"#
+        ));
+        assert!(rendered.contains(
+            r#"http_request_duration_seconds_bucket{method="GET",route="/metrics-test/:id",status="202",le="0.005"}"#
+        ));
+        assert!(!rendered.contains("/metrics-test/1"));
+    }
+
+    #[test]
+    fn test_events_ingested_are_labelled_by_key_source_and_channel() {
+        let handle = handle();
+        record_events_ingested(None, "kafka", 3);
+        record_events_ingested(None, "kafka", 0);
+        record_events_ingested(Some("web"), "api", 2);
+
+        let rendered = handle.render();
+        assert!(rendered.contains(r#"events_ingested_total{source="other",channel="kafka"} 3"#));
+        assert!(rendered.contains(r#"events_ingested_total{source="web",channel="api"} 2"#));
+    }
+}
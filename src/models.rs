@@ -1,10 +1,16 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
+/// The tenant a request acts for, set by the auth middleware from the
+/// caller's token or API key. Handlers take it as `Extension<TenantId>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TenantId(pub Uuid);
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Event {
     pub id: Uuid,
+    pub tenant_id: Uuid,
     pub event_type: String,
     pub source: String,
     pub data: serde_json::Value,
@@ -15,16 +21,111 @@ pub struct Event {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateEventRequest {
     pub event_type: String,
+    /// Ignored for producers authenticating with an API key, whose events
+    /// are stamped with the key's source.
+    #[serde(default)]
     pub source: String,
     pub data: serde_json::Value,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchCreateEventsResponse {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    /// 0-based array index, or 1-based line number for NDJSON bodies.
+    pub index: usize,
+    pub status: BatchItemStatus,
+    pub id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Accepted,
+    Rejected,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AnalyticsQuery {
     pub start_date: DateTime<Utc>,
     pub end_date: DateTime<Utc>,
+    #[serde(default, deserialize_with = "deserialize_list")]
     pub event_types: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_list")]
     pub sources: Option<Vec<String>>,
+    /// Bucket size for `time_series`; picked from the range length when absent.
+    #[serde(default)]
+    pub interval: Option<TimeInterval>,
+    /// IANA time zone used to align buckets, e.g. `Europe/Berlin`. Defaults to UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeInterval {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl TimeInterval {
+    /// The unit name understood by Postgres `date_trunc` and `interval`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInterval::Minute => "minute",
+            TimeInterval::Hour => "hour",
+            TimeInterval::Day => "day",
+            TimeInterval::Week => "week",
+            TimeInterval::Month => "month",
+        }
+    }
+
+    /// Shortest length a bucket of this size can have.
+    pub fn min_duration(&self) -> chrono::Duration {
+        match self {
+            TimeInterval::Minute => chrono::Duration::minutes(1),
+            TimeInterval::Hour => chrono::Duration::hours(1),
+            TimeInterval::Day => chrono::Duration::days(1),
+            TimeInterval::Week => chrono::Duration::weeks(1),
+            TimeInterval::Month => chrono::Duration::days(28),
+        }
+    }
+}
+
+/// Accepts either a JSON array or a comma-separated string, so list filters
+/// work both in JSON bodies and in query strings (`?sources=web,ios`).
+fn deserialize_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum List {
+        Items(Vec<String>),
+        Csv(String),
+    }
+
+    let items = match Option::<List>::deserialize(deserializer)? {
+        Some(List::Items(items)) => items,
+        Some(List::Csv(csv)) => csv
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => return Ok(None),
+    };
+
+    Ok(if items.is_empty() { None } else { Some(items) })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,20 +142,96 @@ pub struct TimeSeriesData {
     pub count: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
+    pub tenant_id: Uuid,
     pub email: String,
     pub name: String,
+    pub role: Role,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// What a user may do. `Admin` passes every role check, `Unassigned` none;
+/// the others each unlock one area of the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    /// Read-only access to analytics.
+    Analyst,
+    /// May only submit events.
+    Ingest,
+    /// May run and inspect news verifications.
+    NewsReviewer,
+    /// Manages contacts and accounts.
+    Sales,
+    /// Can sign in but is granted nothing until an admin assigns a role.
+    Unassigned,
+}
+
+impl Role {
+    pub fn grants(self, required: Role) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Unassigned => false,
+            role => role == required,
+        }
+    }
+}
+
+/// An event producer's credential. The key itself is never stored, only its
+/// hash, so this record is safe to return from admin endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub source: String,
+    pub tenant_id: Uuid,
+    pub key_prefix: String,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyResponse {
+    /// Shown only in this response.
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateUserRoleRequest {
+    pub role: Role,
+}
+
+/// Self-service sign-up: creates a new tenant with the registrant as its
+/// admin.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterRequest {
+    pub organization: String,
+    pub email: String,
+    pub name: String,
+    pub password: String,
+}
+
+/// Adds a user to the calling admin's tenant.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateUserRequest {
     pub email: String,
     pub name: String,
     pub password: String,
+    pub role: Role,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -65,10 +242,492 @@ pub struct LoginRequest {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
+    /// Short-lived access token, sent as `Authorization: Bearer <token>`.
     pub token: String,
+    /// Single-use token exchanged at `/api/v1/auth/refresh` for a new pair.
+    pub refresh_token: String,
+    /// Seconds until `token` expires.
+    pub expires_in: i64,
     pub user: User,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+/// A company the tenant sells to.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Account {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub domain: Option<String>,
+    pub industry: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of account create and update; an update replaces every field.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountRequest {
+    pub name: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub industry: Option<String>,
+}
+
+/// A person at a customer or prospect, optionally filed under an account.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Contact {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of contact create and update; an update replaces every field, so
+/// sending `account_id: null` unlinks the contact from its account.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactRequest {
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub account_id: Option<Uuid>,
+}
+
+/// The kinds of identifier that tie events to a person.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum IdentifierKind {
+    Email,
+    UserId,
+    /// Assigned by a tracker before the visitor is known.
+    AnonymousId,
+    Phone,
+}
+
+impl IdentifierKind {
+    /// The value stored in `identifiers.kind`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentifierKind::Email => "email",
+            IdentifierKind::UserId => "user_id",
+            IdentifierKind::AnonymousId => "anonymous_id",
+            IdentifierKind::Phone => "phone",
+        }
+    }
+}
+
+/// Whether a suggested duplicate pair still awaits review. Merged pairs are
+/// removed along with the merged contact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum DuplicateStatus {
+    Open,
+    Dismissed,
+}
+
+/// Two contacts the duplicate scan believes are the same person.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ContactDuplicate {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub contact_a_id: Uuid,
+    pub contact_b_id: Uuid,
+    /// Between 0 and 1; higher is more likely the same person.
+    pub score: f32,
+    /// Which of `email`, `phone` and `name` matched.
+    pub reasons: Vec<String>,
+    pub status: DuplicateStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A duplicate pair with both contacts, as shown for review.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateSuggestion {
+    #[serde(flatten)]
+    pub duplicate: ContactDuplicate,
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeContactsRequest {
+    /// Folded into the contact named in the path, then deleted.
+    pub merged_contact_id: Uuid,
+}
+
+/// Records repointed from the merged contact to the survivor, kept so the
+/// merge can be undone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MovedRecords {
+    #[serde(default)]
+    pub identities: Vec<Uuid>,
+    #[serde(default)]
+    pub deals: Vec<Uuid>,
+    #[serde(default)]
+    pub activities: Vec<Uuid>,
+    #[serde(default)]
+    pub tasks: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ContactMerge {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub survivor_id: Uuid,
+    pub merged_contact_id: Uuid,
+    pub merged_contact: sqlx::types::Json<Contact>,
+    pub moved_records: sqlx::types::Json<MovedRecords>,
+    pub merged_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub undone_at: Option<DateTime<Utc>>,
+    pub undone_by: Option<Uuid>,
+}
+
+/// A tenant's sales process: an ordered list of stages deals move through.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum StageKind {
+    Open,
+    /// Deals entering the stage are closed as won.
+    Won,
+    /// Deals entering the stage are closed as lost.
+    Lost,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Stage {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub pipeline_id: Uuid,
+    pub name: String,
+    /// 0-based order within the pipeline.
+    pub position: i32,
+    pub kind: StageKind,
+    /// Percent chance of winning a deal in this stage.
+    pub probability: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineDetail {
+    #[serde(flatten)]
+    pub pipeline: Pipeline,
+    pub stages: Vec<Stage>,
+}
+
+/// Body of pipeline create and update. On update the stage list replaces
+/// the current one: stages are matched by `id`, those without one are
+/// added, and those left out are removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineRequest {
+    pub name: String,
+    pub stages: Vec<StageRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StageRequest {
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub name: String,
+    #[serde(default = "default_stage_kind")]
+    pub kind: StageKind,
+    #[serde(default)]
+    pub probability: Option<i32>,
+}
+
+fn default_stage_kind() -> StageKind {
+    StageKind::Open
+}
+
+/// An opportunity moving through a pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Deal {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub pipeline_id: Uuid,
+    pub stage_id: Uuid,
+    pub name: String,
+    /// In the currency's minor unit, e.g. cents.
+    pub expected_value: i64,
+    pub currency: String,
+    pub expected_close_date: Option<NaiveDate>,
+    pub contact_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    /// When the deal entered its current stage.
+    pub stage_changed_at: DateTime<Utc>,
+    /// Set while the deal is in a won or lost stage.
+    pub closed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of deal update, and the shared part of deal create. The stage is
+/// changed through its own endpoint, so it is recorded in the history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DealRequest {
+    pub name: String,
+    #[serde(default)]
+    pub expected_value: i64,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default)]
+    pub expected_close_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub contact_id: Option<Uuid>,
+    #[serde(default)]
+    pub account_id: Option<Uuid>,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateDealRequest {
+    pub pipeline_id: Uuid,
+    /// Defaults to the pipeline's first stage.
+    #[serde(default)]
+    pub stage_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub deal: DealRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MoveDealRequest {
+    pub stage_id: Uuid,
+}
+
+/// `?pipeline_id=&stage_id=` for the deal list.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DealFilter {
+    #[serde(default)]
+    pub pipeline_id: Option<Uuid>,
+    #[serde(default)]
+    pub stage_id: Option<Uuid>,
+}
+
+/// A stage a deal entered. `from_stage_id` is empty for the stage it was
+/// created in, and either stage id is cleared if that stage is removed.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DealStageChange {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub deal_id: Uuid,
+    pub from_stage_id: Option<Uuid>,
+    pub to_stage_id: Option<Uuid>,
+    pub changed_by: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// The CRM record an activity or task is attached to: exactly one of the
+/// ids is set. Also the `?contact_id=&account_id=&deal_id=` list filter,
+/// where any combination narrows the results.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordLink {
+    #[serde(default)]
+    pub contact_id: Option<Uuid>,
+    #[serde(default)]
+    pub account_id: Option<Uuid>,
+    #[serde(default)]
+    pub deal_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ActivityKind {
+    Call,
+    Meeting,
+    Note,
+}
+
+/// A call, meeting or note logged against a contact, account or deal.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Activity {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub kind: ActivityKind,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    pub contact_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub deal_id: Option<Uuid>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of activity update, and the shared part of activity create.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActivityRequest {
+    pub kind: ActivityKind,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Defaults to now.
+    #[serde(default)]
+    pub occurred_at: Option<DateTime<Utc>>,
+}
+
+/// The record an activity is attached to is set on create and kept after.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateActivityRequest {
+    #[serde(flatten)]
+    pub record: RecordLink,
+    #[serde(flatten)]
+    pub activity: ActivityRequest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum TaskStatus {
+    Open,
+    Completed,
+}
+
+/// A follow-up assigned to a user, attached like an `Activity`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Task {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub due_at: DateTime<Utc>,
+    pub assignee_id: Uuid,
+    pub status: TaskStatus,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// When the overdue notification was published, if it has been.
+    pub overdue_notified_at: Option<DateTime<Utc>>,
+    pub contact_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub deal_id: Option<Uuid>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body of task update, and the shared part of task create.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub due_at: DateTime<Utc>,
+    /// Defaults to the user creating the task.
+    #[serde(default)]
+    pub assignee_id: Option<Uuid>,
+    #[serde(default = "default_task_status")]
+    pub status: TaskStatus,
+}
+
+/// As with activities, the record is set on create and kept after.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTaskRequest {
+    #[serde(flatten)]
+    pub record: RecordLink,
+    #[serde(flatten)]
+    pub task: TaskRequest,
+}
+
+fn default_task_status() -> TaskStatus {
+    TaskStatus::Open
+}
+
+/// `?assignee_id=&status=` on top of the `RecordLink` filter for the task
+/// list.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    #[serde(default)]
+    pub assignee_id: Option<Uuid>,
+    #[serde(default)]
+    pub status: Option<TaskStatus>,
+}
+
+/// `?within_days=` for the caller's upcoming tasks.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpcomingTasksQuery {
+    #[serde(default)]
+    pub within_days: Option<u32>,
+}
+
+impl UpcomingTasksQuery {
+    pub const DEFAULT_WITHIN_DAYS: u32 = 7;
+    pub const MAX_WITHIN_DAYS: u32 = 365;
+
+    pub fn within_days(&self) -> u32 {
+        self.within_days.unwrap_or(Self::DEFAULT_WITHIN_DAYS)
+    }
+}
+
+/// `?page=&per_page=` for list endpoints. Pages are 1-based.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PageQuery {
+    #[serde(default)]
+    pub page: Option<u32>,
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+impl PageQuery {
+    pub const DEFAULT_PER_PAGE: u32 = 25;
+    pub const MAX_PER_PAGE: u32 = 100;
+
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(Self::DEFAULT_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page().saturating_sub(1)) * i64::from(self.per_page())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Matching rows across all pages.
+    pub total: i64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,6 +736,7 @@ mod tests {
     fn test_event_serialization() {
         let event = Event {
             id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
             event_type: "page_view".to_string(),
             source: "web".to_string(),
             data: serde_json::json!({
@@ -94,6 +754,32 @@ mod tests {
         assert_eq!(event.source, deserialized.source);
     }
 
+    #[test]
+    fn test_role_grants() {
+        assert!(Role::Admin.grants(Role::NewsReviewer));
+        assert!(Role::Analyst.grants(Role::Analyst));
+        assert!(!Role::Analyst.grants(Role::Ingest));
+        assert!(!Role::Ingest.grants(Role::Admin));
+        assert!(!Role::Unassigned.grants(Role::Unassigned));
+
+        assert_eq!(
+            serde_json::from_str::<Role>("\"news_reviewer\"").unwrap(),
+            Role::NewsReviewer
+        );
+    }
+
+    #[test]
+    fn test_page_query_offsets() {
+        assert_eq!(PageQuery::default().offset(), 0);
+        assert_eq!(PageQuery::default().per_page(), PageQuery::DEFAULT_PER_PAGE);
+
+        let query = PageQuery {
+            page: Some(3),
+            per_page: Some(10),
+        };
+        assert_eq!(query.offset(), 20);
+    }
+
     #[test]
     fn test_analytics_query_validation() {
         let query = AnalyticsQuery {
@@ -101,6 +787,8 @@ mod tests {
             end_date: Utc::now(),
             event_types: Some(vec!["page_view".to_string()]),
             sources: Some(vec!["web".to_string()]),
+            interval: Some(TimeInterval::Hour),
+            timezone: Some("UTC".to_string()),
         };
 
         let serialized = serde_json::to_string(&query).unwrap();
@@ -110,5 +798,25 @@ mod tests {
             query.event_types.unwrap()[0],
             deserialized.event_types.unwrap()[0]
         );
+        assert_eq!(deserialized.interval, Some(TimeInterval::Hour));
+    }
+
+    #[test]
+    fn test_analytics_query_from_query_string() {
+        let query: AnalyticsQuery = serde_json::from_value(serde_json::json!({
+            "start_date": "2024-01-01T00:00:00Z",
+            "end_date": "2024-01-02T00:00:00Z",
+            "sources": "web, ios,",
+            "interval": "week",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            query.sources,
+            Some(vec!["web".to_string(), "ios".to_string()])
+        );
+        assert!(query.event_types.is_none());
+        assert_eq!(query.interval, Some(TimeInterval::Week));
+        assert!(query.timezone.is_none());
     }
 } 
\ No newline at end of file
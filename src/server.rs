@@ -0,0 +1,114 @@
+use crate::config::{MetricsConfig, ServerConfig, TlsConfig};
+use axum::Router;
+use std::io;
+use tokio::net::TcpListener;
+
+/// Serves `app` on the configured address until SIGTERM or Ctrl-C, then
+/// stops accepting connections and waits for in-flight requests to finish.
+/// TLS is terminated with rustls when `tls` is configured.
+pub async fn serve(app: Router, config: &ServerConfig) -> io::Result<()> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+    let addr = listener.local_addr()?;
+
+    match &config.tls {
+        None => {
+            tracing::info!("listening on http://{}", addr);
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        }
+        Some(tls) => {
+            let rustls_config = load_tls(tls).await?;
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
+
+            tracing::info!("listening on https://{}", addr);
+            axum_server::from_tcp_rustls(listener.into_std()?, rustls_config)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+        }
+    }
+}
+
+/// Serves `app`, which only exposes `/metrics`, over plain HTTP on the
+/// metrics address until shutdown.
+pub async fn serve_metrics(app: Router, config: MetricsConfig) -> io::Result<()> {
+    let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+    tracing::info!("serving metrics on http://{}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+}
+
+async fn load_tls(tls: &TlsConfig) -> io::Result<axum_server::tls_rustls::RustlsConfig> {
+    axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+        .await
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "Failed to load TLS certificate {} / key {}: {}",
+                    tls.cert_path.display(),
+                    tls.key_path.display(),
+                    e
+                ),
+            )
+        })
+}
+
+/// Resolves on SIGTERM (as sent by container orchestrators) or Ctrl-C.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("shutdown signal received, draining in-flight requests");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_missing_tls_files_are_reported() {
+        let tls = TlsConfig {
+            cert_path: PathBuf::from("does/not/exist.pem"),
+            key_path: PathBuf::from("does/not/exist.key"),
+        };
+
+        let error = load_tls(&tls).await.unwrap_err();
+        assert!(error.to_string().contains("does/not/exist.pem"));
+    }
+}
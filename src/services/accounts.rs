@@ -0,0 +1,174 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{Account, AccountRequest, Page, PageQuery},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const ACCOUNT_COLUMNS: &str = "id, tenant_id, name, domain, industry, created_at, updated_at";
+
+/// Expects a request already checked by `api::accounts::validate_account`.
+pub async fn create_account(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &AccountRequest,
+) -> Result<Account> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let account = sqlx::query_as::<_, Account>(&format!(
+        r#"
+        INSERT INTO accounts (id, tenant_id, name, domain, industry)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING {}
+        "#,
+        ACCOUNT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(request.name.trim())
+    .bind(normalize_domain(request.domain.as_deref()))
+    .bind(trimmed(request.industry.as_deref()))
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(account)
+}
+
+pub async fn list_accounts(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    page: &PageQuery,
+) -> Result<Page<Account>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Account>(&format!(
+        r#"
+        SELECT {} FROM accounts
+        WHERE tenant_id = $1
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+        "#,
+        ACCOUNT_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Accounts of other tenants are reported as missing.
+pub async fn get_account(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<Account> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let account = sqlx::query_as::<_, Account>(&format!(
+        "SELECT {} FROM accounts WHERE id = $1 AND tenant_id = $2",
+        ACCOUNT_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    account.ok_or_else(|| not_found(id))
+}
+
+pub async fn update_account(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &AccountRequest,
+) -> Result<Account> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let account = sqlx::query_as::<_, Account>(&format!(
+        r#"
+        UPDATE accounts
+        SET name = $3, domain = $4, industry = $5, updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        ACCOUNT_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.name.trim())
+    .bind(normalize_domain(request.domain.as_deref()))
+    .bind(trimmed(request.industry.as_deref()))
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    account.ok_or_else(|| not_found(id))
+}
+
+/// Contacts filed under the account are kept and unlinked.
+pub async fn delete_account(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deleted = sqlx::query("DELETE FROM accounts WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Account {} not found", id))
+}
+
+/// Blank optional fields are stored as NULL.
+pub fn trimmed(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Stores `Example.com` and `https://www.example.com/` alike as `example.com`.
+fn normalize_domain(domain: Option<&str>) -> Option<String> {
+    let domain = trimmed(domain)?.to_lowercase();
+    let domain = domain
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.");
+    let domain = domain.split('/').next().unwrap_or_default();
+    (!domain.is_empty()).then(|| domain.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(
+            normalize_domain(Some(" https://www.Example.com/about ")),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            normalize_domain(Some("acme.io")),
+            Some("acme.io".to_string())
+        );
+        assert_eq!(normalize_domain(Some("   ")), None);
+        assert_eq!(normalize_domain(None), None);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{Activity, ActivityRequest, CreateActivityRequest, Page, PageQuery, RecordLink},
+    services::{
+        accounts::{self, trimmed},
+        contacts, deals,
+    },
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const ACTIVITY_COLUMNS: &str = "id, tenant_id, kind, subject, body, occurred_at, contact_id, \
+     account_id, deal_id, created_by, created_at, updated_at";
+
+/// Expects a request already checked by `api::activities::validate_activity`.
+pub async fn create_activity(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &CreateActivityRequest,
+    created_by: Uuid,
+) -> Result<Activity> {
+    ensure_record(pool, tenant_id, &request.record).await?;
+
+    let activity = &request.activity;
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let activity = sqlx::query_as::<_, Activity>(&format!(
+        r#"
+        INSERT INTO activities
+            (id, tenant_id, kind, subject, body, occurred_at, contact_id, account_id, deal_id,
+             created_by)
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, NOW()), $7, $8, $9, $10)
+        RETURNING {}
+        "#,
+        ACTIVITY_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(activity.kind)
+    .bind(trimmed(activity.subject.as_deref()))
+    .bind(trimmed(activity.body.as_deref()))
+    .bind(activity.occurred_at)
+    .bind(request.record.contact_id)
+    .bind(request.record.account_id)
+    .bind(request.record.deal_id)
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(activity)
+}
+
+/// The tenant's activities, newest first, narrowed to the records in
+/// `filter`.
+pub async fn list_activities(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    filter: &RecordLink,
+    page: &PageQuery,
+) -> Result<Page<Activity>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Activity>(&format!(
+        r#"
+        SELECT {} FROM activities
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR contact_id = $2)
+          AND ($3::uuid IS NULL OR account_id = $3)
+          AND ($4::uuid IS NULL OR deal_id = $4)
+        ORDER BY occurred_at DESC, id DESC
+        LIMIT $5 OFFSET $6
+        "#,
+        ACTIVITY_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(filter.contact_id)
+    .bind(filter.account_id)
+    .bind(filter.deal_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM activities
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR contact_id = $2)
+          AND ($3::uuid IS NULL OR account_id = $3)
+          AND ($4::uuid IS NULL OR deal_id = $4)
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(filter.contact_id)
+    .bind(filter.account_id)
+    .bind(filter.deal_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Activities of other tenants are reported as missing.
+pub async fn get_activity(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<Activity> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let activity = sqlx::query_as::<_, Activity>(&format!(
+        "SELECT {} FROM activities WHERE id = $1 AND tenant_id = $2",
+        ACTIVITY_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    activity.ok_or_else(|| not_found(id))
+}
+
+/// Replaces the activity's fields; an omitted `occurred_at` keeps the
+/// current one.
+pub async fn update_activity(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &ActivityRequest,
+) -> Result<Activity> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let activity = sqlx::query_as::<_, Activity>(&format!(
+        r#"
+        UPDATE activities
+        SET kind = $3, subject = $4, body = $5, occurred_at = COALESCE($6, occurred_at),
+            updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        ACTIVITY_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.kind)
+    .bind(trimmed(request.subject.as_deref()))
+    .bind(trimmed(request.body.as_deref()))
+    .bind(request.occurred_at)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    activity.ok_or_else(|| not_found(id))
+}
+
+pub async fn delete_activity(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deleted = sqlx::query("DELETE FROM activities WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+/// Checks that the linked record belongs to the tenant, which foreign keys
+/// alone would not. Shared with tasks.
+pub async fn ensure_record(pool: &PgPool, tenant_id: Uuid, record: &RecordLink) -> Result<()> {
+    let found = match *record {
+        RecordLink {
+            contact_id: Some(id),
+            ..
+        } => contacts::get_contact(pool, tenant_id, id).await.map(drop),
+        RecordLink {
+            account_id: Some(id),
+            ..
+        } => accounts::get_account(pool, tenant_id, id).await.map(drop),
+        RecordLink {
+            deal_id: Some(id), ..
+        } => deals::get_deal(pool, tenant_id, id).await.map(drop),
+        RecordLink { .. } => Ok(()),
+    };
+    match found {
+        Err(AppError::NotFound(message)) => Err(AppError::Validation(message)),
+        other => other,
+    }
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Activity {} not found", id))
+}
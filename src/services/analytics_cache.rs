@@ -0,0 +1,261 @@
+use crate::models::{AnalyticsQuery, AnalyticsResponse, TimeInterval};
+use chrono::{DateTime, Duration, Utc};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+use uuid::Uuid;
+
+const KEY_PREFIX: &str = "analytics:v1";
+
+/// Events are stamped with the database `NOW()` at transaction start, so a
+/// range that ended within this margin may still receive late commits.
+const LIVE_MARGIN_MINUTES: i64 = 5;
+
+const LIVE_TTL_SECONDS: u64 = 60;
+const RECENT_TTL_SECONDS: u64 = 15 * 60;
+const HISTORICAL_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+/// Caches `AnalyticsResponse`s in Redis. Cache failures are logged and
+/// treated as misses so Redis outages never fail analytics requests.
+#[derive(Clone)]
+pub struct AnalyticsCache {
+    connection: ConnectionManager,
+}
+
+impl AnalyticsCache {
+    pub fn new(connection: ConnectionManager) -> Self {
+        Self { connection }
+    }
+
+    /// The key to cache a response under. Keys of live ranges carry the
+    /// tenant's current generation, so bumping it in `invalidate_live`
+    /// orphans them; a response computed while the generation moves on is
+    /// stored under the old one and never served. `None` skips the cache.
+    pub async fn versioned_key(
+        &self,
+        key: String,
+        tenant_id: Uuid,
+        end_date: DateTime<Utc>,
+    ) -> Option<String> {
+        let (_, live) = ttl_for(end_date, Utc::now());
+        if !live {
+            return Some(key);
+        }
+
+        let mut connection = self.connection.clone();
+        match connection
+            .get::<_, Option<u64>>(generation_key(tenant_id))
+            .await
+        {
+            Ok(generation) => Some(with_generation(&key, generation.unwrap_or(0))),
+            Err(e) => {
+                tracing::warn!(error = %e, "analytics cache read failed");
+                None
+            }
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Option<AnalyticsResponse> {
+        let mut connection = self.connection.clone();
+        let cached: Option<String> = match connection.get(key).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                tracing::warn!(error = %e, "analytics cache read failed");
+                return None;
+            }
+        };
+
+        cached.and_then(|json| serde_json::from_str(&json).ok())
+    }
+
+    pub async fn put(&self, key: &str, end_date: DateTime<Utc>, response: &AnalyticsResponse) {
+        let json = match serde_json::to_string(response) {
+            Ok(json) => json,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to encode analytics response");
+                return;
+            }
+        };
+
+        let (ttl, _) = ttl_for(end_date, Utc::now());
+        let mut connection = self.connection.clone();
+        if let Err(e) = connection.set_ex::<_, _, ()>(key, json, ttl).await {
+            tracing::warn!(error = %e, "analytics cache write failed");
+        }
+    }
+
+    /// Drops every cached response of the tenant whose range could include
+    /// newly ingested events. Historical ranges are left alone since they
+    /// cannot change; orphaned live entries expire with their short TTL.
+    pub async fn invalidate_live(&self, tenant_id: Uuid) {
+        let mut connection = self.connection.clone();
+        if let Err(e) = connection
+            .incr::<_, _, ()>(generation_key(tenant_id), 1)
+            .await
+        {
+            tracing::warn!(error = %e, "analytics cache invalidation failed");
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CanonicalQuery<'a> {
+    start_date: i64,
+    end_date: i64,
+    event_types: Option<Vec<&'a str>>,
+    sources: Option<Vec<&'a str>>,
+    interval: &'a str,
+    timezone: &'a str,
+}
+
+/// Counter bumped whenever the tenant's live ranges may have changed. It
+/// never expires, so a reset cannot bring back entries of an old generation.
+fn generation_key(tenant_id: Uuid) -> String {
+    format!("{}:{}:generation", KEY_PREFIX, tenant_id)
+}
+
+fn with_generation(key: &str, generation: u64) -> String {
+    format!("{}:g{}", key, generation)
+}
+
+/// A key that is identical for every query producing the same response,
+/// regardless of filter order or duplicates. Keys are namespaced by tenant
+/// so one tenant can never be served another's cached response.
+pub fn cache_key(
+    tenant_id: Uuid,
+    query: &AnalyticsQuery,
+    interval: TimeInterval,
+    timezone: &str,
+) -> String {
+    let canonical = CanonicalQuery {
+        start_date: query.start_date.timestamp_micros(),
+        end_date: query.end_date.timestamp_micros(),
+        event_types: canonical_list(&query.event_types),
+        sources: canonical_list(&query.sources),
+        interval: interval.as_str(),
+        timezone,
+    };
+
+    // Serializing a struct of strings and integers cannot fail.
+    let json = serde_json::to_vec(&canonical).unwrap_or_default();
+    format!(
+        "{}:{}:{}",
+        KEY_PREFIX,
+        tenant_id,
+        hex::encode(Sha3_256::digest(json))
+    )
+}
+
+fn canonical_list(list: &Option<Vec<String>>) -> Option<Vec<&str>> {
+    list.as_ref().map(|items| {
+        let mut items: Vec<&str> = items.iter().map(String::as_str).collect();
+        items.sort_unstable();
+        items.dedup();
+        items
+    })
+}
+
+/// TTL in seconds, and whether the range is still live. The further back a
+/// range ends, the less likely it is to change, so the longer it is kept.
+fn ttl_for(end_date: DateTime<Utc>, now: DateTime<Utc>) -> (u64, bool) {
+    if end_date > now - Duration::minutes(LIVE_MARGIN_MINUTES) {
+        (LIVE_TTL_SECONDS, true)
+    } else if end_date > now - Duration::days(1) {
+        (RECENT_TTL_SECONDS, false)
+    } else {
+        (HISTORICAL_TTL_SECONDS, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(event_types: Option<Vec<&str>>, sources: Option<Vec<&str>>) -> AnalyticsQuery {
+        let to_owned = |list: Option<Vec<&str>>| {
+            list.map(|items| items.into_iter().map(str::to_string).collect())
+        };
+
+        AnalyticsQuery {
+            start_date: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            end_date: DateTime::from_timestamp(1_700_086_400, 0).unwrap(),
+            event_types: to_owned(event_types),
+            sources: to_owned(sources),
+            interval: None,
+            timezone: None,
+        }
+    }
+
+    const TENANT: Uuid = Uuid::from_u128(1);
+
+    #[test]
+    fn test_cache_key_is_canonical() {
+        let a = query(Some(vec!["signup", "page_view"]), Some(vec!["web"]));
+        let b = query(Some(vec!["page_view", "signup", "page_view"]), Some(vec!["web"]));
+        assert_eq!(
+            cache_key(TENANT, &a, TimeInterval::Hour, "UTC"),
+            cache_key(TENANT, &b, TimeInterval::Hour, "UTC")
+        );
+    }
+
+    #[test]
+    fn test_cache_key_distinguishes_queries() {
+        let base = query(None, None);
+        let key = cache_key(TENANT, &base, TimeInterval::Hour, "UTC");
+
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_ne!(key, cache_key(TENANT, &base, TimeInterval::Day, "UTC"));
+        assert_ne!(
+            key,
+            cache_key(TENANT, &base, TimeInterval::Hour, "Europe/Berlin")
+        );
+        assert_ne!(
+            key,
+            cache_key(
+                TENANT,
+                &query(Some(vec!["web"]), None),
+                TimeInterval::Hour,
+                "UTC"
+            )
+        );
+        assert_ne!(
+            key,
+            cache_key(
+                TENANT,
+                &query(None, Some(vec!["web"])),
+                TimeInterval::Hour,
+                "UTC"
+            )
+        );
+    }
+
+    #[test]
+    fn test_cache_keys_are_namespaced_by_tenant() {
+        let base = query(None, None);
+        let other = Uuid::from_u128(2);
+        let key = cache_key(TENANT, &base, TimeInterval::Hour, "UTC");
+
+        assert_ne!(key, cache_key(other, &base, TimeInterval::Hour, "UTC"));
+        assert!(key.contains(&TENANT.to_string()));
+        assert_ne!(generation_key(TENANT), generation_key(other));
+        assert_ne!(generation_key(TENANT), key);
+    }
+
+    #[test]
+    fn test_generations_give_distinct_keys() {
+        let key = cache_key(TENANT, &query(None, None), TimeInterval::Hour, "UTC");
+
+        assert!(with_generation(&key, 0).starts_with(&key));
+        assert_ne!(with_generation(&key, 0), with_generation(&key, 1));
+        assert_ne!(with_generation(&key, 1), with_generation(&key, 10));
+    }
+
+    #[test]
+    fn test_ttl_grows_with_range_age() {
+        let now = Utc::now();
+        assert_eq!(ttl_for(now + Duration::hours(1), now), (LIVE_TTL_SECONDS, true));
+        assert_eq!(ttl_for(now - Duration::minutes(1), now), (LIVE_TTL_SECONDS, true));
+        assert_eq!(ttl_for(now - Duration::hours(2), now), (RECENT_TTL_SECONDS, false));
+        assert_eq!(ttl_for(now - Duration::days(30), now), (HISTORICAL_TTL_SECONDS, false));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::ApiKey,
+};
+use sha3::{Digest, Sha3_256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Marks keys issued by this service so they are recognisable in logs and
+/// secret scanners.
+const KEY_PREFIX: &str = "ccrm_";
+
+/// Characters of the plaintext key kept in clear so admins can tell keys
+/// apart without ever seeing the secret again.
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+/// `last_used_at` is only refreshed once per interval so busy producers do
+/// not turn every request into a row update.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+const API_KEY_COLUMNS: &str =
+    "id, name, source, tenant_id, key_prefix, created_by, created_at, last_used_at, revoked_at";
+
+/// A newly generated key: the plaintext is returned to the caller once and
+/// only its hash is stored.
+pub struct GeneratedKey {
+    pub plaintext: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+pub fn generate_key() -> GeneratedKey {
+    // Two v4 UUIDs give 244 bits from the OS random source
+    let plaintext = format!(
+        "{}{}{}",
+        KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+
+    GeneratedKey {
+        prefix: plaintext[..DISPLAY_PREFIX_LENGTH].to_string(),
+        hash: hash_key(&plaintext),
+        plaintext,
+    }
+}
+
+/// Keys carry enough entropy that a fast hash is sufficient, which keeps
+/// the per-request lookup cheap.
+pub fn hash_key(plaintext: &str) -> String {
+    hex::encode(Sha3_256::digest(plaintext.as_bytes()))
+}
+
+pub async fn create_api_key(
+    pool: &PgPool,
+    name: &str,
+    source: &str,
+    tenant_id: Uuid,
+    created_by: Uuid,
+) -> Result<(ApiKey, String)> {
+    let key = generate_key();
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!(
+        r#"
+        INSERT INTO api_keys (id, name, source, tenant_id, key_prefix, key_hash, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        API_KEY_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(name)
+    .bind(source)
+    .bind(tenant_id)
+    .bind(&key.prefix)
+    .bind(&key.hash)
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok((api_key, key.plaintext))
+}
+
+pub async fn list_api_keys(pool: &PgPool, tenant_id: Uuid) -> Result<Vec<ApiKey>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let keys = sqlx::query_as::<_, ApiKey>(&format!(
+        "SELECT {} FROM api_keys WHERE tenant_id = $1 ORDER BY created_at DESC",
+        API_KEY_COLUMNS
+    ))
+    .bind(tenant_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(keys)
+}
+
+pub async fn revoke_api_key(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<ApiKey> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let api_key = sqlx::query_as::<_, ApiKey>(&format!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = COALESCE(revoked_at, NOW())
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        API_KEY_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    api_key.ok_or_else(|| AppError::NotFound(format!("API key {} not found", id)))
+}
+
+/// Resolves a presented key to its active record and records the use.
+pub async fn authenticate(pool: &PgPool, plaintext: &str) -> Result<ApiKey> {
+    let mut tx = db::begin_credential_lookup(pool).await?;
+    let mut api_key = sqlx::query_as::<_, ApiKey>(&format!(
+        "SELECT {} FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+        API_KEY_COLUMNS
+    ))
+    .bind(hash_key(plaintext))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Auth("Invalid API key".to_string()))?;
+
+    let now = chrono::Utc::now();
+    let stale = api_key
+        .last_used_at
+        .is_none_or(|last| (now - last).num_seconds() >= LAST_USED_RESOLUTION_SECONDS);
+    if stale {
+        db::set_tenant(&mut tx, api_key.tenant_id).await?;
+        sqlx::query("UPDATE api_keys SET last_used_at = $2 WHERE id = $1")
+            .bind(api_key.id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        api_key.last_used_at = Some(now);
+    }
+    tx.commit().await?;
+
+    Ok(api_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_key() {
+        let key = generate_key();
+
+        assert!(key.plaintext.starts_with(KEY_PREFIX));
+        assert_eq!(key.plaintext.len(), KEY_PREFIX.len() + 64);
+        assert!(key.plaintext.starts_with(&key.prefix));
+        assert_eq!(key.hash, hash_key(&key.plaintext));
+        assert_ne!(key.hash, generate_key().hash);
+    }
+
+    #[test]
+    fn test_hash_key_is_stable_hex() {
+        let hash = hash_key("ccrm_example");
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(hash, hash_key("ccrm_example"));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    config::AuthConfig,
+    error::{AppError, Result},
+    models::User,
+};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use chrono::Utc;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    /// Login session the token was issued for; revoking it invalidates
+    /// every access token issued under it.
+    pub sid: Uuid,
+    pub tenant_id: Uuid,
+    pub email: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Password hashing and JWT issuance/validation.
+#[derive(Clone)]
+pub struct AuthService {
+    keys: Arc<Keys>,
+    token_ttl_seconds: i64,
+}
+
+struct Keys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl AuthService {
+    pub fn new(config: &AuthConfig) -> Self {
+        let secret = config.jwt_secret.as_bytes();
+        Self {
+            keys: Arc::new(Keys {
+                encoding: EncodingKey::from_secret(secret),
+                decoding: DecodingKey::from_secret(secret),
+            }),
+            token_ttl_seconds: config.token_ttl_seconds,
+        }
+    }
+
+    /// Argon2 is deliberately slow, so hashing runs on the blocking pool.
+    pub async fn hash_password(&self, password: &str) -> Result<String> {
+        let password = password.to_string();
+        tokio::task::spawn_blocking(move || {
+            let salt = SaltString::generate(&mut OsRng);
+            Argon2::default()
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Password hashing task failed: {}", e)))?
+    }
+
+    pub async fn verify_password(&self, password: &str, password_hash: &str) -> Result<bool> {
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+        tokio::task::spawn_blocking(move || {
+            let parsed = PasswordHash::new(&password_hash)
+                .map_err(|e| AppError::Internal(format!("Invalid stored password hash: {}", e)))?;
+            Ok(Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok())
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Password verification task failed: {}", e)))?
+    }
+
+    pub fn token_ttl_seconds(&self) -> i64 {
+        self.token_ttl_seconds
+    }
+
+    pub fn issue_token(&self, user: &User, session_id: Uuid) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user.id,
+            sid: session_id,
+            tenant_id: user.tenant_id,
+            email: user.email.clone(),
+            iat: now,
+            exp: now + self.token_ttl_seconds,
+        };
+
+        jsonwebtoken::encode(&Header::default(), &claims, &self.keys.encoding)
+            .map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))
+    }
+
+    pub fn validate_token(&self, token: &str) -> Result<Claims> {
+        jsonwebtoken::decode::<Claims>(token, &self.keys.decoding, &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|e| AppError::Auth(format!("Invalid token: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Role;
+
+    fn service(token_ttl_seconds: i64) -> AuthService {
+        AuthService::new(&AuthConfig {
+            jwt_secret: "test-secret".to_string(),
+            token_ttl_seconds,
+            refresh_token_ttl_seconds: 86400,
+            bootstrap_admin_email: None,
+            allow_public_registration: false,
+        })
+    }
+
+    fn user() -> User {
+        User {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            email: "jane@example.com".to_string(),
+            name: "Jane".to_string(),
+            role: Role::Analyst,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_password_round_trip() {
+        let service = service(3600);
+        let hash = service.hash_password("correct horse").await.unwrap();
+
+        assert_ne!(hash, "correct horse");
+        assert!(service
+            .verify_password("correct horse", &hash)
+            .await
+            .unwrap());
+        assert!(!service.verify_password("wrong horse", &hash).await.unwrap());
+    }
+
+    #[test]
+    fn test_token_round_trip() {
+        let service = service(3600);
+        let user = user();
+        let session_id = Uuid::new_v4();
+
+        let claims = service
+            .validate_token(&service.issue_token(&user, session_id).unwrap())
+            .unwrap();
+        assert_eq!(claims.sub, user.id);
+        assert_eq!(claims.sid, session_id);
+        assert_eq!(claims.tenant_id, user.tenant_id);
+        assert_eq!(claims.email, user.email);
+    }
+
+    #[test]
+    fn test_rejects_expired_and_foreign_tokens() {
+        let expired = service(-3600).issue_token(&user(), Uuid::new_v4()).unwrap();
+        assert!(matches!(
+            service(3600).validate_token(&expired),
+            Err(AppError::Auth(_))
+        ));
+
+        let foreign = AuthService::new(&AuthConfig {
+            jwt_secret: "other-secret".to_string(),
+            token_ttl_seconds: 3600,
+            refresh_token_ttl_seconds: 86400,
+            bootstrap_admin_email: None,
+            allow_public_registration: false,
+        })
+        .issue_token(&user(), Uuid::new_v4())
+        .unwrap();
+        assert!(matches!(
+            service(3600).validate_token(&foreign),
+            Err(AppError::Auth(_))
+        ));
+    }
+}
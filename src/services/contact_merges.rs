@@ -0,0 +1,288 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{Contact, ContactMerge, MovedRecords, Page, PageQuery},
+    services::contacts::CONTACT_COLUMNS,
+};
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+
+const MERGE_COLUMNS: &str = "id, tenant_id, survivor_id, merged_contact_id, merged_contact, \
+     moved_records, merged_by, created_at, undone_at, undone_by";
+
+/// Folds `merged_id` into `survivor_id`: the merged contact's identities, and
+/// with them its event timeline, and its deals, activities and tasks move to
+/// the survivor, and the merged contact is deleted. The survivor's own fields
+/// are left as they are.
+pub async fn merge_contacts(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    survivor_id: Uuid,
+    merged_id: Uuid,
+    merged_by: Uuid,
+) -> Result<ContactMerge> {
+    if survivor_id == merged_id {
+        return Err(AppError::Validation(
+            "A contact cannot be merged into itself".to_string(),
+        ));
+    }
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let contacts = sqlx::query_as::<_, Contact>(&format!(
+        "SELECT {} FROM contacts WHERE tenant_id = $1 AND id = ANY($2) ORDER BY id FOR UPDATE",
+        CONTACT_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind([survivor_id, merged_id])
+    .fetch_all(&mut *tx)
+    .await?;
+    for id in [survivor_id, merged_id] {
+        if !contacts.iter().any(|contact| contact.id == id) {
+            return Err(AppError::NotFound(format!("Contact {} not found", id)));
+        }
+    }
+    let merged = contacts
+        .into_iter()
+        .find(|contact| contact.id == merged_id)
+        .expect("merged contact was just checked");
+
+    let identities = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE identities SET contact_id = $1, updated_at = NOW()
+        WHERE contact_id = $2
+        RETURNING id
+        "#,
+    )
+    .bind(survivor_id)
+    .bind(merged_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let deals = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE deals SET contact_id = $1, updated_at = NOW()
+        WHERE contact_id = $2
+        RETURNING id
+        "#,
+    )
+    .bind(survivor_id)
+    .bind(merged_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let activities = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE activities SET contact_id = $1, updated_at = NOW()
+        WHERE contact_id = $2
+        RETURNING id
+        "#,
+    )
+    .bind(survivor_id)
+    .bind(merged_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let tasks = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        UPDATE tasks SET contact_id = $1, updated_at = NOW()
+        WHERE contact_id = $2
+        RETURNING id
+        "#,
+    )
+    .bind(survivor_id)
+    .bind(merged_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let moved_records = MovedRecords {
+        identities,
+        deals,
+        activities,
+        tasks,
+    };
+
+    sqlx::query("DELETE FROM contacts WHERE id = $1")
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let merge = sqlx::query_as::<_, ContactMerge>(&format!(
+        r#"
+        INSERT INTO contact_merges
+            (id, tenant_id, survivor_id, merged_contact_id, merged_contact, moved_records, merged_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        MERGE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(survivor_id)
+    .bind(merged_id)
+    .bind(Json(&merged))
+    .bind(Json(&moved_records))
+    .bind(merged_by)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(%tenant_id, %survivor_id, %merged_id, "contacts merged");
+    Ok(merge)
+}
+
+/// The tenant's merges, newest first, undone ones included.
+pub async fn list_merges(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    page: &PageQuery,
+) -> Result<Page<ContactMerge>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, ContactMerge>(&format!(
+        r#"
+        SELECT {} FROM contact_merges
+        WHERE tenant_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        MERGE_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM contact_merges WHERE tenant_id = $1")
+        .bind(tenant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Restores the merged contact as it was and moves its records back. Events
+/// resolved since the merge follow the identity they were resolved to.
+/// Fails while the survivor has itself been merged away, since its records
+/// would be elsewhere; that later merge has to be undone first.
+pub async fn undo_merge(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    merge_id: Uuid,
+    undone_by: Uuid,
+) -> Result<Contact> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let merge = sqlx::query_as::<_, ContactMerge>(&format!(
+        "SELECT {} FROM contact_merges WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        MERGE_COLUMNS
+    ))
+    .bind(merge_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Contact merge {} not found", merge_id)))?;
+
+    if merge.undone_at.is_some() {
+        return Err(AppError::Conflict(format!(
+            "Contact merge {} has already been undone",
+            merge_id
+        )));
+    }
+    let survivor_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM contacts WHERE id = $1 AND tenant_id = $2 FOR UPDATE)",
+    )
+    .bind(merge.survivor_id)
+    .bind(tenant_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !survivor_exists {
+        return Err(AppError::Conflict(format!(
+            "Contact {} no longer exists; undo the merge that removed it first",
+            merge.survivor_id
+        )));
+    }
+
+    // The account may have been deleted since, like any account a contact is filed under
+    let merged = &merge.merged_contact.0;
+    let contact = sqlx::query_as::<_, Contact>(&format!(
+        r#"
+        INSERT INTO contacts
+            (id, tenant_id, account_id, first_name, last_name, email, phone, title,
+             created_at, updated_at)
+        VALUES ($1, $2, (SELECT id FROM accounts WHERE id = $3 AND tenant_id = $2),
+            $4, $5, $6, $7, $8, $9, NOW())
+        RETURNING {}
+        "#,
+        CONTACT_COLUMNS
+    ))
+    .bind(merged.id)
+    .bind(tenant_id)
+    .bind(merged.account_id)
+    .bind(&merged.first_name)
+    .bind(&merged.last_name)
+    .bind(&merged.email)
+    .bind(&merged.phone)
+    .bind(&merged.title)
+    .bind(merged.created_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE identities SET contact_id = $1, updated_at = NOW()
+        WHERE id = ANY($2) AND contact_id = $3
+        "#,
+    )
+    .bind(merged.id)
+    .bind(&merge.moved_records.identities)
+    .bind(merge.survivor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE deals SET contact_id = $1, updated_at = NOW()
+        WHERE id = ANY($2) AND contact_id = $3
+        "#,
+    )
+    .bind(merged.id)
+    .bind(&merge.moved_records.deals)
+    .bind(merge.survivor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE activities SET contact_id = $1, updated_at = NOW()
+        WHERE id = ANY($2) AND contact_id = $3
+        "#,
+    )
+    .bind(merged.id)
+    .bind(&merge.moved_records.activities)
+    .bind(merge.survivor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE tasks SET contact_id = $1, updated_at = NOW()
+        WHERE id = ANY($2) AND contact_id = $3
+        "#,
+    )
+    .bind(merged.id)
+    .bind(&merge.moved_records.tasks)
+    .bind(merge.survivor_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE contact_merges SET undone_at = NOW(), undone_by = $2 WHERE id = $1")
+        .bind(merge_id)
+        .bind(undone_by)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    tracing::info!(%tenant_id, %merge_id, "contact merge undone");
+    Ok(contact)
+}
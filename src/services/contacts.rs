@@ -0,0 +1,193 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{Contact, ContactRequest, Page, PageQuery},
+    services::{
+        accounts::{self, trimmed},
+        identities,
+    },
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const CONTACT_COLUMNS: &str = "id, tenant_id, account_id, first_name, last_name, email, \
+     phone, title, created_at, updated_at";
+
+/// Expects a request already checked by `api::contacts::validate_contact`.
+/// Events already seen with the contact's email or phone join its timeline.
+pub async fn create_contact(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &ContactRequest,
+) -> Result<Contact> {
+    ensure_account(pool, tenant_id, request.account_id).await?;
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let contact = sqlx::query_as::<_, Contact>(&format!(
+        r#"
+        INSERT INTO contacts (id, tenant_id, account_id, first_name, last_name, email, phone, title)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING {}
+        "#,
+        CONTACT_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(request.account_id)
+    .bind(request.first_name.trim())
+    .bind(request.last_name.trim())
+    .bind(normalize_email(request.email.as_deref()))
+    .bind(trimmed(request.phone.as_deref()))
+    .bind(trimmed(request.title.as_deref()))
+    .fetch_one(&mut *tx)
+    .await?;
+    identities::link_contact(&mut tx, &contact).await?;
+    tx.commit().await?;
+
+    Ok(contact)
+}
+
+/// Lists the tenant's contacts, or only those filed under `account_id`.
+pub async fn list_contacts(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    account_id: Option<Uuid>,
+    page: &PageQuery,
+) -> Result<Page<Contact>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Contact>(&format!(
+        r#"
+        SELECT {} FROM contacts
+        WHERE tenant_id = $1 AND ($2::uuid IS NULL OR account_id = $2)
+        ORDER BY created_at, id
+        LIMIT $3 OFFSET $4
+        "#,
+        CONTACT_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(account_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM contacts WHERE tenant_id = $1 AND ($2::uuid IS NULL OR account_id = $2)",
+    )
+    .bind(tenant_id)
+    .bind(account_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Contacts of other tenants are reported as missing.
+pub async fn get_contact(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<Contact> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let contact = sqlx::query_as::<_, Contact>(&format!(
+        "SELECT {} FROM contacts WHERE id = $1 AND tenant_id = $2",
+        CONTACT_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    contact.ok_or_else(|| not_found(id))
+}
+
+pub async fn update_contact(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &ContactRequest,
+) -> Result<Contact> {
+    ensure_account(pool, tenant_id, request.account_id).await?;
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let contact = sqlx::query_as::<_, Contact>(&format!(
+        r#"
+        UPDATE contacts
+        SET account_id = $3, first_name = $4, last_name = $5, email = $6, phone = $7,
+            title = $8, updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        CONTACT_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.account_id)
+    .bind(request.first_name.trim())
+    .bind(request.last_name.trim())
+    .bind(normalize_email(request.email.as_deref()))
+    .bind(trimmed(request.phone.as_deref()))
+    .bind(trimmed(request.title.as_deref()))
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| not_found(id))?;
+    identities::link_contact(&mut tx, &contact).await?;
+    tx.commit().await?;
+
+    Ok(contact)
+}
+
+pub async fn delete_contact(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deleted = sqlx::query("DELETE FROM contacts WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+/// The foreign key alone would accept another tenant's account.
+async fn ensure_account(pool: &PgPool, tenant_id: Uuid, account_id: Option<Uuid>) -> Result<()> {
+    let Some(account_id) = account_id else {
+        return Ok(());
+    };
+
+    match accounts::get_account(pool, tenant_id, account_id).await {
+        Ok(_) => Ok(()),
+        Err(AppError::NotFound(message)) => Err(AppError::Validation(message)),
+        Err(e) => Err(e),
+    }
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Contact {} not found", id))
+}
+
+pub fn normalize_email(email: Option<&str>) -> Option<String> {
+    trimmed(email).map(|email| email.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(
+            normalize_email(Some("  Jane.Doe@Example.COM ")),
+            Some("jane.doe@example.com".to_string())
+        );
+        assert_eq!(normalize_email(Some("")), None);
+        assert_eq!(normalize_email(None), None);
+    }
+}
@@ -0,0 +1,413 @@
+use crate::{
+    api::events::new_event,
+    db,
+    error::{AppError, Result},
+    metrics,
+    models::{
+        CreateDealRequest, CreateEventRequest, Deal, DealFilter, DealRequest, DealStageChange,
+        Event, Page, PageQuery, Stage, StageKind,
+    },
+    services::{accounts, contacts, event_store, identities::IdentityResolver, pipelines},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+const DEAL_COLUMNS: &str = "id, tenant_id, pipeline_id, stage_id, name, expected_value, currency, \
+     expected_close_date, contact_id, account_id, stage_changed_at, closed_at, created_at, \
+     updated_at";
+
+const STAGE_CHANGE_COLUMNS: &str =
+    "id, tenant_id, deal_id, from_stage_id, to_stage_id, changed_by, changed_at";
+
+/// Event type recorded whenever a deal moves to another stage.
+pub const DEAL_STAGE_CHANGED: &str = "deal_stage_changed";
+
+/// Source of the events the CRM records itself.
+const EVENT_SOURCE: &str = "crm";
+
+/// Expects a request already checked by `api::deals::validate_deal`. The
+/// deal's first stage is recorded in its history.
+pub async fn create_deal(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &CreateDealRequest,
+    created_by: Uuid,
+) -> Result<Deal> {
+    ensure_links(pool, tenant_id, &request.deal).await?;
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let stage = match request.stage_id {
+        Some(stage_id) => {
+            pipelines::get_stage(&mut tx, tenant_id, request.pipeline_id, stage_id).await?
+        }
+        None => pipelines::first_stage(&mut tx, tenant_id, request.pipeline_id).await?,
+    };
+
+    let deal = &request.deal;
+    let deal = sqlx::query_as::<_, Deal>(&format!(
+        r#"
+        INSERT INTO deals
+            (id, tenant_id, pipeline_id, stage_id, name, expected_value, currency,
+             expected_close_date, contact_id, account_id, closed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        DEAL_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(request.pipeline_id)
+    .bind(stage.id)
+    .bind(deal.name.trim())
+    .bind(deal.expected_value)
+    .bind(deal.currency.trim().to_uppercase())
+    .bind(deal.expected_close_date)
+    .bind(deal.contact_id)
+    .bind(deal.account_id)
+    .bind(closed_at(&stage, None, Utc::now()))
+    .fetch_one(&mut *tx)
+    .await?;
+
+    record_stage_change(&mut tx, &deal, None, created_by).await?;
+    tx.commit().await?;
+
+    Ok(deal)
+}
+
+pub async fn list_deals(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    filter: &DealFilter,
+    page: &PageQuery,
+) -> Result<Page<Deal>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Deal>(&format!(
+        r#"
+        SELECT {} FROM deals
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR pipeline_id = $2)
+          AND ($3::uuid IS NULL OR stage_id = $3)
+        ORDER BY created_at, id
+        LIMIT $4 OFFSET $5
+        "#,
+        DEAL_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(filter.pipeline_id)
+    .bind(filter.stage_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM deals
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR pipeline_id = $2)
+          AND ($3::uuid IS NULL OR stage_id = $3)
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(filter.pipeline_id)
+    .bind(filter.stage_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Deals of other tenants are reported as missing.
+pub async fn get_deal(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<Deal> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deal = sqlx::query_as::<_, Deal>(&format!(
+        "SELECT {} FROM deals WHERE id = $1 AND tenant_id = $2",
+        DEAL_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    deal.ok_or_else(|| not_found(id))
+}
+
+/// Replaces the deal's fields; its pipeline and stage are left as they are.
+pub async fn update_deal(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &DealRequest,
+) -> Result<Deal> {
+    ensure_links(pool, tenant_id, request).await?;
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deal = sqlx::query_as::<_, Deal>(&format!(
+        r#"
+        UPDATE deals
+        SET name = $3, expected_value = $4, currency = $5, expected_close_date = $6,
+            contact_id = $7, account_id = $8, updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        DEAL_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.name.trim())
+    .bind(request.expected_value)
+    .bind(request.currency.trim().to_uppercase())
+    .bind(request.expected_close_date)
+    .bind(request.contact_id)
+    .bind(request.account_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    deal.ok_or_else(|| not_found(id))
+}
+
+pub async fn delete_deal(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deleted = sqlx::query("DELETE FROM deals WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+/// Moves the deal to another stage of its pipeline and records the change,
+/// both in the deal's history and as a `deal_stage_changed` event stored in
+/// the same transaction. The caller publishes the returned event.
+pub async fn move_deal(
+    pool: &PgPool,
+    resolver: &IdentityResolver,
+    tenant_id: Uuid,
+    id: Uuid,
+    stage_id: Uuid,
+    moved_by: Uuid,
+) -> Result<(Deal, Event)> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deal = sqlx::query_as::<_, Deal>(&format!(
+        "SELECT {} FROM deals WHERE id = $1 AND tenant_id = $2 FOR UPDATE",
+        DEAL_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| not_found(id))?;
+    if deal.stage_id == stage_id {
+        return Err(AppError::Validation(format!(
+            "Deal {} is already in stage {}",
+            id, stage_id
+        )));
+    }
+
+    let from = pipelines::get_stage(&mut tx, tenant_id, deal.pipeline_id, deal.stage_id).await?;
+    let to = pipelines::get_stage(&mut tx, tenant_id, deal.pipeline_id, stage_id).await?;
+    let now = Utc::now();
+    let moved = sqlx::query_as::<_, Deal>(&format!(
+        r#"
+        UPDATE deals
+        SET stage_id = $2, stage_changed_at = $3, closed_at = $4, updated_at = $3
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        DEAL_COLUMNS
+    ))
+    .bind(id)
+    .bind(to.id)
+    .bind(now)
+    .bind(closed_at(&to, deal.closed_at, now))
+    .fetch_one(&mut *tx)
+    .await?;
+    record_stage_change(&mut tx, &moved, Some(from.id), moved_by).await?;
+
+    let request = stage_changed_event(&deal, &from, &to, moved_by, now);
+    let event =
+        event_store::insert_event_in(&mut tx, resolver, new_event(request, tenant_id)?).await?;
+    tx.commit().await?;
+
+    metrics::record_events_ingested(None, "crm", 1);
+    Ok((moved, event))
+}
+
+/// The deal's stage history, oldest first.
+pub async fn stage_history(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> Result<Vec<DealStageChange>> {
+    get_deal(pool, tenant_id, id).await?;
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let history = sqlx::query_as::<_, DealStageChange>(&format!(
+        "SELECT {} FROM deal_stage_changes WHERE deal_id = $1 ORDER BY changed_at, id",
+        STAGE_CHANGE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(history)
+}
+
+async fn record_stage_change(
+    tx: &mut Transaction<'static, Postgres>,
+    deal: &Deal,
+    from_stage_id: Option<Uuid>,
+    changed_by: Uuid,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO deal_stage_changes
+            (id, tenant_id, deal_id, from_stage_id, to_stage_id, changed_by, changed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(deal.tenant_id)
+    .bind(deal.id)
+    .bind(from_stage_id)
+    .bind(deal.stage_id)
+    .bind(changed_by)
+    .bind(deal.stage_changed_at)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Carries what sales velocity reports need: the stages on either side and
+/// how long the deal spent in the one it left.
+fn stage_changed_event(
+    deal: &Deal,
+    from: &Stage,
+    to: &Stage,
+    moved_by: Uuid,
+    moved_at: DateTime<Utc>,
+) -> CreateEventRequest {
+    CreateEventRequest {
+        event_type: DEAL_STAGE_CHANGED.to_string(),
+        source: EVENT_SOURCE.to_string(),
+        data: serde_json::json!({
+            "deal_id": deal.id,
+            "pipeline_id": deal.pipeline_id,
+            "from_stage_id": from.id,
+            "from_stage": from.name,
+            "to_stage_id": to.id,
+            "to_stage": to.name,
+            "to_stage_kind": to.kind,
+            "expected_value": deal.expected_value,
+            "currency": deal.currency,
+            "seconds_in_stage": (moved_at - deal.stage_changed_at).num_seconds(),
+            "changed_by": moved_by,
+        }),
+    }
+}
+
+/// Deals close on entering a won or lost stage and reopen on leaving it.
+/// Moving between closed stages, e.g. correcting won to lost, keeps the
+/// original close date.
+fn closed_at(
+    stage: &Stage,
+    closed_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    (stage.kind != StageKind::Open).then(|| closed_at.unwrap_or(now))
+}
+
+/// Foreign keys alone would accept another tenant's contact or account.
+async fn ensure_links(pool: &PgPool, tenant_id: Uuid, request: &DealRequest) -> Result<()> {
+    let contact = match request.contact_id {
+        Some(id) => contacts::get_contact(pool, tenant_id, id).await.map(drop),
+        None => Ok(()),
+    };
+    let account = match request.account_id {
+        Some(id) => accounts::get_account(pool, tenant_id, id).await.map(drop),
+        None => Ok(()),
+    };
+    match contact.and(account) {
+        Err(AppError::NotFound(message)) => Err(AppError::Validation(message)),
+        other => other,
+    }
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Deal {} not found", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn stage(name: &str, kind: StageKind) -> Stage {
+        Stage {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::nil(),
+            pipeline_id: Uuid::nil(),
+            name: name.to_string(),
+            position: 0,
+            kind,
+            probability: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_stage_changed_event() {
+        let (from, to) = (
+            stage("Qualified", StageKind::Open),
+            stage("Won", StageKind::Won),
+        );
+        let now = Utc::now();
+        let deal = Deal {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::nil(),
+            pipeline_id: Uuid::nil(),
+            stage_id: from.id,
+            name: "Renewal".to_string(),
+            expected_value: 125_000,
+            currency: "EUR".to_string(),
+            expected_close_date: None,
+            contact_id: None,
+            account_id: None,
+            stage_changed_at: now - Duration::hours(2),
+            closed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let request = stage_changed_event(&deal, &from, &to, Uuid::nil(), now);
+        assert_eq!(request.event_type, DEAL_STAGE_CHANGED);
+        assert_eq!(request.data["from_stage"], "Qualified");
+        assert_eq!(request.data["to_stage_kind"], "won");
+        assert_eq!(request.data["seconds_in_stage"], 7200);
+        assert!(crate::api::events::validate_event(&request).is_ok());
+
+        assert_eq!(closed_at(&to, None, now), Some(now));
+        assert_eq!(closed_at(&from, None, now), None);
+        let closed = now - Duration::days(3);
+        let lost = stage("Lost", StageKind::Lost);
+        assert_eq!(closed_at(&lost, Some(closed), now), Some(closed));
+        assert_eq!(closed_at(&from, Some(closed), now), None);
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{
+    config::DuplicatesConfig,
+    db,
+    error::{AppError, Result},
+    models::{Contact, ContactDuplicate, DuplicateSuggestion, Page, PageQuery},
+    services::contacts::CONTACT_COLUMNS,
+};
+use sqlx::PgPool;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// Each signal moves the score towards 1 by its weight, so a pair matching
+/// on email and name scores higher than one matching on email alone.
+const EMAIL_WEIGHT: f32 = 0.9;
+const PHONE_WEIGHT: f32 = 0.8;
+const NAME_WEIGHT: f32 = 0.5;
+
+/// Jaro-Winkler similarity from which names count as matching.
+const MIN_NAME_SIMILARITY: f64 = 0.85;
+
+/// Shorter numbers are likely extensions or placeholders.
+const MIN_PHONE_DIGITS: usize = 7;
+
+/// Contacts sharing a very common key, such as a shared inbox, are not
+/// paired up, since that would be quadratic in the bucket size.
+const MAX_BUCKET_SIZE: usize = 200;
+
+const DUPLICATE_COLUMNS: &str = "id, tenant_id, contact_a_id, contact_b_id, score, reasons, \
+     status, created_at, updated_at";
+
+/// Periodically scores every tenant's contacts for likely duplicates and
+/// stores the pairs for review.
+pub struct DuplicateScanner {
+    pool: PgPool,
+    interval: Duration,
+    min_score: f32,
+}
+
+impl DuplicateScanner {
+    pub fn new(pool: PgPool, config: &DuplicatesConfig) -> Self {
+        Self {
+            pool,
+            interval: Duration::from_secs(config.scan_interval_seconds),
+            min_score: config.min_score,
+        }
+    }
+
+    pub async fn run(self) {
+        tracing::info!(interval = ?self.interval, "duplicate scanner started");
+
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.scan_all().await {
+                tracing::error!(error = %e, "duplicate scan failed");
+            }
+        }
+    }
+
+    async fn scan_all(&self) -> Result<()> {
+        let tenant_ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM tenants")
+            .fetch_all(&self.pool)
+            .await?;
+
+        for tenant_id in tenant_ids {
+            match scan_tenant(&self.pool, tenant_id, self.min_score).await {
+                Ok(Some(pairs)) => tracing::debug!(%tenant_id, pairs, "scanned for duplicates"),
+                Ok(None) => tracing::debug!(%tenant_id, "duplicate scan already running"),
+                Err(e) => tracing::error!(%tenant_id, error = %e, "duplicate scan failed"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces the tenant's open suggestions with the pairs scoring at least
+/// `min_score`. Dismissed pairs stay dismissed. Returns `None` when another
+/// instance is scanning the tenant.
+pub async fn scan_tenant(pool: &PgPool, tenant_id: Uuid, min_score: f32) -> Result<Option<usize>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let locked: bool =
+        sqlx::query_scalar("SELECT pg_try_advisory_xact_lock(hashtext('duplicates:' || $1::text))")
+            .bind(tenant_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let contacts = sqlx::query_as::<_, Contact>(&format!(
+        "SELECT {} FROM contacts WHERE tenant_id = $1",
+        CONTACT_COLUMNS
+    ))
+    .bind(tenant_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let pairs = find_duplicates(&contacts, min_score);
+    let mut contact_a_ids = Vec::with_capacity(pairs.len());
+    let mut contact_b_ids = Vec::with_capacity(pairs.len());
+    let mut scores = Vec::with_capacity(pairs.len());
+    let mut reasons = Vec::with_capacity(pairs.len());
+    for pair in &pairs {
+        contact_a_ids.push(pair.contact_a_id);
+        contact_b_ids.push(pair.contact_b_id);
+        scores.push(pair.score);
+        reasons.push(pair.reasons.join(","));
+    }
+
+    sqlx::query(
+        r#"
+        DELETE FROM contact_duplicates
+        WHERE tenant_id = $1 AND status = 'open'
+          AND (contact_a_id, contact_b_id) NOT IN (
+            SELECT * FROM UNNEST($2::uuid[], $3::uuid[])
+          )
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(&contact_a_ids)
+    .bind(&contact_b_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO contact_duplicates (id, tenant_id, contact_a_id, contact_b_id, score, reasons)
+        SELECT gen_random_uuid(), $1, contact_a_id, contact_b_id, score,
+            string_to_array(reasons, ',')
+        FROM UNNEST($2::uuid[], $3::uuid[], $4::real[], $5::text[])
+            AS pair (contact_a_id, contact_b_id, score, reasons)
+        ON CONFLICT (tenant_id, contact_a_id, contact_b_id) DO UPDATE
+        SET score = EXCLUDED.score, reasons = EXCLUDED.reasons, updated_at = NOW()
+        WHERE contact_duplicates.status = 'open'
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(&contact_a_ids)
+    .bind(&contact_b_ids)
+    .bind(&scores)
+    .bind(&reasons)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(pairs.len()))
+}
+
+/// Open suggestions, most likely duplicates first.
+pub async fn list_suggestions(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    page: &PageQuery,
+) -> Result<Page<DuplicateSuggestion>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let duplicates = sqlx::query_as::<_, ContactDuplicate>(&format!(
+        r#"
+        SELECT {} FROM contact_duplicates
+        WHERE tenant_id = $1 AND status = 'open'
+        ORDER BY score DESC, id
+        LIMIT $2 OFFSET $3
+        "#,
+        DUPLICATE_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM contact_duplicates WHERE tenant_id = $1 AND status = 'open'",
+    )
+    .bind(tenant_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let contact_ids: Vec<Uuid> = duplicates
+        .iter()
+        .flat_map(|duplicate| [duplicate.contact_a_id, duplicate.contact_b_id])
+        .collect();
+    let contacts: HashMap<Uuid, Contact> = sqlx::query_as::<_, Contact>(&format!(
+        "SELECT {} FROM contacts WHERE tenant_id = $1 AND id = ANY($2)",
+        CONTACT_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(&contact_ids)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|contact| (contact.id, contact))
+    .collect();
+    tx.commit().await?;
+
+    let items = duplicates
+        .into_iter()
+        .map(|duplicate| DuplicateSuggestion {
+            contacts: [duplicate.contact_a_id, duplicate.contact_b_id]
+                .iter()
+                .filter_map(|id| contacts.get(id).cloned())
+                .collect(),
+            duplicate,
+        })
+        .collect();
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Marks the pair as not a duplicate; later scans leave it dismissed.
+pub async fn dismiss(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<ContactDuplicate> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let duplicate = sqlx::query_as::<_, ContactDuplicate>(&format!(
+        r#"
+        UPDATE contact_duplicates
+        SET status = 'dismissed', updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        DUPLICATE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    duplicate.ok_or_else(|| AppError::NotFound(format!("Duplicate suggestion {} not found", id)))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ScoredPair {
+    pub contact_a_id: Uuid,
+    pub contact_b_id: Uuid,
+    pub score: f32,
+    pub reasons: Vec<&'static str>,
+}
+
+struct MatchKeys {
+    id: Uuid,
+    name: String,
+    email: Option<String>,
+    phone: Option<String>,
+}
+
+impl MatchKeys {
+    fn new(contact: &Contact) -> Self {
+        let name = format!("{} {}", contact.first_name.trim(), contact.last_name.trim());
+        let phone: Option<String> = contact
+            .phone
+            .as_deref()
+            .map(|phone| phone.chars().filter(char::is_ascii_digit).collect())
+            .filter(|digits: &String| digits.len() >= MIN_PHONE_DIGITS);
+        Self {
+            id: contact.id,
+            name: name.to_lowercase(),
+            email: contact
+                .email
+                .as_deref()
+                .map(|email| email.trim().to_lowercase()),
+            phone,
+        }
+    }
+
+    /// Pairs are only scored within a block: contacts sharing an email, a
+    /// phone, or the start of their last name and first initial.
+    fn blocks(&self, contact: &Contact) -> Vec<String> {
+        let mut blocks = Vec::with_capacity(3);
+        if let Some(email) = &self.email {
+            blocks.push(format!("email:{}", email));
+        }
+        if let Some(phone) = &self.phone {
+            blocks.push(format!("phone:{}", phone));
+        }
+        let last: String = contact
+            .last_name
+            .trim()
+            .to_lowercase()
+            .chars()
+            .take(2)
+            .collect();
+        let first = contact.first_name.trim().to_lowercase().chars().next();
+        if let (false, Some(first)) = (last.is_empty(), first) {
+            blocks.push(format!("name:{}:{}", last, first));
+        }
+        blocks
+    }
+}
+
+/// Every pair scoring at least `min_score`, with the lower id first.
+pub fn find_duplicates(contacts: &[Contact], min_score: f32) -> Vec<ScoredPair> {
+    let keys: Vec<MatchKeys> = contacts.iter().map(MatchKeys::new).collect();
+    let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, contact) in contacts.iter().enumerate() {
+        for block in keys[index].blocks(contact) {
+            blocks.entry(block).or_default().push(index);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut pairs = Vec::new();
+    for members in blocks.values() {
+        if members.len() > MAX_BUCKET_SIZE {
+            continue;
+        }
+        for (i, &a) in members.iter().enumerate() {
+            for &b in &members[i + 1..] {
+                let (a, b) = if keys[a].id < keys[b].id {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                if !seen.insert((a, b)) {
+                    continue;
+                }
+                let (score, reasons) = score_pair(&keys[a], &keys[b]);
+                if score >= min_score {
+                    pairs.push(ScoredPair {
+                        contact_a_id: keys[a].id,
+                        contact_b_id: keys[b].id,
+                        score,
+                        reasons,
+                    });
+                }
+            }
+        }
+    }
+    pairs.sort_by_key(|pair| (pair.contact_a_id, pair.contact_b_id));
+    pairs
+}
+
+fn score_pair(a: &MatchKeys, b: &MatchKeys) -> (f32, Vec<&'static str>) {
+    let mut unlikely = 1.0;
+    let mut reasons = Vec::new();
+
+    if a.email.is_some() && a.email == b.email {
+        unlikely *= 1.0 - EMAIL_WEIGHT;
+        reasons.push("email");
+    }
+    if a.phone.is_some() && a.phone == b.phone {
+        unlikely *= 1.0 - PHONE_WEIGHT;
+        reasons.push("phone");
+    }
+    let similarity = strsim::jaro_winkler(&a.name, &b.name);
+    if similarity >= MIN_NAME_SIMILARITY {
+        unlikely *= 1.0 - NAME_WEIGHT * similarity as f32;
+        reasons.push("name");
+    }
+
+    (1.0 - unlikely, reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn contact(
+        id: u128,
+        first: &str,
+        last: &str,
+        email: Option<&str>,
+        phone: Option<&str>,
+    ) -> Contact {
+        Contact {
+            id: Uuid::from_u128(id),
+            tenant_id: Uuid::nil(),
+            account_id: None,
+            first_name: first.to_string(),
+            last_name: last.to_string(),
+            email: email.map(str::to_string),
+            phone: phone.map(str::to_string),
+            title: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates_scores_each_signal() {
+        let contacts = [
+            contact(1, "Jane", "Doe", Some("jane@example.com"), None),
+            contact(2, "Jane", "Doe", Some("jane@example.com"), None),
+            contact(3, "J.", "Smith", None, Some("+1 (555) 010-9999")),
+            contact(4, "Bob", "Jones", None, Some("15550109999")),
+            contact(5, "Jon", "Smyth", None, None),
+            contact(6, "John", "Smyth", None, None),
+            contact(7, "Alice", "Doe", None, None),
+        ];
+
+        let pairs = find_duplicates(&contacts, 0.4);
+        let found: Vec<(u128, u128, Vec<&str>)> = pairs
+            .iter()
+            .map(|pair| {
+                (
+                    pair.contact_a_id.as_u128(),
+                    pair.contact_b_id.as_u128(),
+                    pair.reasons.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, 2, vec!["email", "name"]),
+                (3, 4, vec!["phone"]),
+                (5, 6, vec!["name"]),
+            ]
+        );
+        assert!((pairs[0].score - 0.95).abs() < 1e-6);
+        assert!((pairs[1].score - PHONE_WEIGHT).abs() < 1e-6);
+        assert!(pairs[2].score < NAME_WEIGHT);
+
+        // A similar but not identical name alone falls below the default threshold
+        assert_eq!(find_duplicates(&contacts, 0.5).len(), 2);
+    }
+}
@@ -0,0 +1,523 @@
+use crate::{
+    api::events::new_event,
+    config::KafkaConfig,
+    error::{AppError, Result},
+    metrics,
+    models::CreateEventRequest,
+    services::{
+        analytics_cache::AnalyticsCache,
+        event_store::{self, NewEvent},
+        identities::IdentityResolver,
+    },
+};
+use rdkafka::{
+    consumer::{CommitMode, Consumer, StreamConsumer},
+    message::{Header, OwnedHeaders, OwnedMessage},
+    producer::{FutureProducer, FutureRecord},
+    Message, Offset, TopicPartitionList,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    future::Future,
+    time::Duration,
+};
+use uuid::Uuid;
+
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Namespace of the v5 ids given to consumed events, derived from where the
+/// message was read so a redelivered message maps to the same event.
+const DELIVERY_NAMESPACE: Uuid = Uuid::from_u128(0x6f3c_9a1e_52d4_4b7e_9c0a_1d2e_3f40_5a6b);
+
+/// Wire format of consumed messages: a `CreateEventRequest` plus the tenant
+/// it belongs to.
+#[derive(Deserialize)]
+struct IngestMessage {
+    tenant_id: Uuid,
+    #[serde(flatten)]
+    event: CreateEventRequest,
+}
+
+/// Reads `IngestMessage` payloads from the configured topics and writes
+/// them to Postgres in batches.
+///
+/// Offsets are committed only after every message of a batch is durable,
+/// either as a stored event or as a record on the dead-letter topic, so a
+/// crash replays the batch instead of losing it (at-least-once delivery).
+/// Replayed messages keep their event id and are not stored twice.
+pub struct EventConsumer {
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    pool: PgPool,
+    cache: AnalyticsCache,
+    resolver: IdentityResolver,
+    dead_letter_topic: String,
+    batch_size: usize,
+    batch_timeout: Duration,
+}
+
+impl EventConsumer {
+    pub fn new(
+        consumer: StreamConsumer,
+        producer: FutureProducer,
+        pool: PgPool,
+        cache: AnalyticsCache,
+        resolver: IdentityResolver,
+        config: &KafkaConfig,
+    ) -> Self {
+        Self {
+            consumer,
+            producer,
+            pool,
+            cache,
+            resolver,
+            dead_letter_topic: config.dead_letter_topic.clone(),
+            batch_size: config.consumer_batch_size.max(1),
+            batch_timeout: Duration::from_millis(config.consumer_batch_timeout_ms),
+        }
+    }
+
+    pub async fn run(self) {
+        tracing::info!(
+            dead_letter_topic = %self.dead_letter_topic,
+            batch_size = self.batch_size,
+            "event consumer started"
+        );
+
+        loop {
+            let batch = self.next_batch().await;
+            self.process(batch).await;
+        }
+    }
+
+    /// Waits for the first message, then collects more until the batch is
+    /// full or `batch_timeout` has elapsed.
+    async fn next_batch(&self) -> Vec<OwnedMessage> {
+        let mut batch = Vec::with_capacity(self.batch_size);
+
+        loop {
+            match self.consumer.recv().await {
+                Ok(message) => {
+                    batch.push(message.detach());
+                    break;
+                }
+                Err(e) => tracing::warn!(error = %e, "kafka consumer error"),
+            }
+        }
+
+        let deadline = tokio::time::Instant::now() + self.batch_timeout;
+        while batch.len() < self.batch_size {
+            match tokio::time::timeout_at(deadline, self.consumer.recv()).await {
+                Ok(Ok(message)) => batch.push(message.detach()),
+                Ok(Err(e)) => tracing::warn!(error = %e, "kafka consumer error"),
+                Err(_) => break,
+            }
+        }
+
+        batch
+    }
+
+    async fn process(&self, batch: Vec<OwnedMessage>) {
+        let offsets = offsets_to_commit(&batch);
+
+        let mut decoded = Vec::with_capacity(batch.len());
+        let mut dead_letters = Vec::new();
+        for message in batch {
+            match decode(&message) {
+                Ok(event) => decoded.push((message, event)),
+                Err(reason) => dead_letters.push((message, reason)),
+            }
+        }
+
+        // An unknown tenant would fail the whole insert on its foreign key,
+        // and keep failing on every retry, so those events are dead-lettered.
+        let tenant_ids: Vec<Uuid> = decoded
+            .iter()
+            .map(|(_, event)| event.tenant_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let known: HashSet<Uuid> = retry("look up tenants", || {
+            event_store::existing_tenants(&self.pool, &tenant_ids)
+        })
+        .await
+        .into_iter()
+        .collect();
+
+        let mut messages = Vec::with_capacity(decoded.len());
+        let mut events = Vec::with_capacity(decoded.len());
+        for (message, event) in decoded {
+            if known.contains(&event.tenant_id) {
+                messages.push(message);
+                events.push(event);
+            } else {
+                let reason = format!("unknown tenant: {}", event.tenant_id);
+                dead_letters.push((message, reason));
+            }
+        }
+
+        let (stored, rejected) = insert_isolating(events.len(), |indexes| {
+            let batch = indexes.iter().map(|&index| events[index].clone()).collect();
+            event_store::insert_events(&self.pool, &self.resolver, batch)
+        })
+        .await;
+        let mut rejected: HashMap<usize, AppError> = rejected.into_iter().collect();
+        for (index, message) in messages.into_iter().enumerate() {
+            if let Some(e) = rejected.remove(&index) {
+                dead_letters.push((message, format!("rejected by database: {}", e)));
+            }
+        }
+        metrics::record_events_ingested(None, "kafka", stored.len());
+        let touched: BTreeSet<Uuid> = stored.iter().map(|event| event.tenant_id).collect();
+        for tenant_id in touched {
+            self.cache.invalidate_live(tenant_id).await;
+        }
+
+        for (message, reason) in &dead_letters {
+            retry("publish dead letter", || self.dead_letter(message, reason)).await;
+        }
+
+        if let Err(e) = self.consumer.commit(&offsets, CommitMode::Sync) {
+            // The batch is durable; a failed commit only means it is replayed.
+            tracing::error!(error = %e, "failed to commit consumer offsets");
+        }
+
+        tracing::debug!(
+            stored = stored.len(),
+            dead_lettered = dead_letters.len(),
+            "consumed event batch"
+        );
+    }
+
+    async fn dead_letter(&self, message: &OwnedMessage, reason: &str) -> Result<()> {
+        let partition = message.partition().to_string();
+        let offset = message.offset().to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "x-error",
+                value: Some(reason),
+            })
+            .insert(Header {
+                key: "x-source-topic",
+                value: Some(message.topic()),
+            })
+            .insert(Header {
+                key: "x-source-partition",
+                value: Some(partition.as_str()),
+            })
+            .insert(Header {
+                key: "x-source-offset",
+                value: Some(offset.as_str()),
+            });
+
+        let mut record = FutureRecord::<[u8], [u8]>::to(&self.dead_letter_topic).headers(headers);
+        if let Some(key) = message.key() {
+            record = record.key(key);
+        }
+        if let Some(payload) = message.payload() {
+            record = record.payload(payload);
+        }
+
+        self.producer
+            .send(record, QUEUE_TIMEOUT)
+            .await
+            .map_err(|(e, _)| {
+                metrics::record_kafka_delivery_failure(&self.dead_letter_topic);
+                AppError::Kafka(e)
+            })?;
+
+        tracing::warn!(
+            topic = message.topic(),
+            partition = message.partition(),
+            offset = message.offset(),
+            reason,
+            "message routed to dead-letter topic"
+        );
+        Ok(())
+    }
+}
+
+fn decode(message: &OwnedMessage) -> std::result::Result<NewEvent, String> {
+    let payload = message
+        .payload()
+        .ok_or_else(|| "message has no payload".to_string())?;
+    let ingest: IngestMessage =
+        serde_json::from_slice(payload).map_err(|e| format!("invalid payload: {}", e))?;
+    let mut event = new_event(ingest.event, ingest.tenant_id).map_err(|e| e.to_string())?;
+    event.id = delivery_id(message);
+    Ok(event)
+}
+
+fn delivery_id(message: &OwnedMessage) -> Uuid {
+    let name = format!(
+        "{}/{}/{}",
+        message.topic(),
+        message.partition(),
+        message.offset()
+    );
+    Uuid::new_v5(&DELIVERY_NAMESPACE, name.as_bytes())
+}
+
+/// Inserts the items numbered `0..len` through `insert`, retrying transient
+/// failures. When the database rejects a batch outright, it is split in
+/// halves until the offending items are isolated, so one bad row cannot
+/// hold up its partition. Returns what was stored and the rejected items.
+async fn insert_isolating<T, F, Fut>(len: usize, mut insert: F) -> (Vec<T>, Vec<(usize, AppError)>)
+where
+    F: FnMut(Vec<usize>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let mut stored = Vec::new();
+    let mut rejected = Vec::new();
+    let mut pending = vec![(0..len).collect::<Vec<_>>()];
+    while let Some(mut batch) = pending.pop() {
+        if batch.is_empty() {
+            continue;
+        }
+        match retry_transient("insert consumed events", || insert(batch.clone())).await {
+            Ok(rows) => stored.extend(rows),
+            Err(e) if batch.len() == 1 => rejected.push((batch[0], e)),
+            Err(e) => {
+                tracing::warn!(error = %e, size = batch.len(), "batch rejected, splitting");
+                let second = batch.split_off(batch.len() / 2);
+                pending.push(second);
+                pending.push(batch);
+            }
+        }
+    }
+    (stored, rejected)
+}
+
+/// Errors retrying cannot fix because Postgres rejects the rows themselves:
+/// SQLSTATE class 22 (data exception, e.g. a NUL in text or jsonb) or 23
+/// (integrity constraint violation).
+fn is_permanent(error: &AppError) -> bool {
+    match error {
+        AppError::Database(sqlx::Error::Database(e)) => e
+            .code()
+            .is_some_and(|code| code.starts_with("22") || code.starts_with("23")),
+        _ => false,
+    }
+}
+
+/// The next offset to read for every partition seen in the batch.
+fn offsets_to_commit(batch: &[OwnedMessage]) -> TopicPartitionList {
+    let mut latest: HashMap<(&str, i32), i64> = HashMap::new();
+    for message in batch {
+        let offset = latest
+            .entry((message.topic(), message.partition()))
+            .or_insert(message.offset());
+        *offset = (*offset).max(message.offset());
+    }
+
+    let mut offsets = TopicPartitionList::with_capacity(latest.len());
+    for ((topic, partition), offset) in latest {
+        // Only offsets without a raw representation are rejected; `Offset::Offset` always has one.
+        let _ = offsets.add_partition_offset(topic, partition, Offset::Offset(offset + 1));
+    }
+    offsets
+}
+
+/// Retries `op` with exponential backoff until it succeeds. Used for writes
+/// the consumer cannot skip without losing messages.
+async fn retry<T, F, Fut>(operation: &str, mut op: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = INITIAL_RETRY_BACKOFF;
+    loop {
+        match op().await {
+            Ok(value) => return value,
+            Err(e) => {
+                tracing::error!(error = %e, ?backoff, "{} failed, retrying", operation);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Like `retry`, but gives up on errors `is_permanent` reports.
+async fn retry_transient<T, F, Fut>(operation: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = INITIAL_RETRY_BACKOFF;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if is_permanent(&e) => return Err(e),
+            Err(e) => {
+                tracing::error!(error = %e, ?backoff, "{} failed, retrying", operation);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdkafka::Timestamp;
+    use std::{borrow::Cow, error::Error as StdError, fmt};
+
+    /// A database error carrying only a SQLSTATE, as Postgres reports them.
+    #[derive(Debug)]
+    struct SqlState(&'static str);
+
+    impl fmt::Display for SqlState {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl StdError for SqlState {}
+
+    impl sqlx::error::DatabaseError for SqlState {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    fn database_error(code: &'static str) -> AppError {
+        AppError::Database(sqlx::Error::Database(Box::new(SqlState(code))))
+    }
+
+    fn message(topic: &str, partition: i32, offset: i64, payload: Option<&str>) -> OwnedMessage {
+        OwnedMessage::new(
+            payload.map(|p| p.as_bytes().to_vec()),
+            None,
+            topic.to_string(),
+            Timestamp::NotAvailable,
+            partition,
+            offset,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_decode() {
+        let valid = message(
+            "crm",
+            0,
+            0,
+            Some(
+                r#"{"tenant_id":"00000000-0000-0000-0000-000000000001","event_type":"page_view","source":"web","data":{}}"#,
+            ),
+        );
+        let event = decode(&valid).unwrap();
+        assert_eq!(event.event_type, "page_view");
+        assert_eq!(event.tenant_id, Uuid::from_u128(1));
+        // A redelivery of the same message gets the same id
+        assert_eq!(decode(&valid).unwrap().id, event.id);
+        assert_ne!(delivery_id(&message("crm", 1, 0, None)), event.id);
+
+        assert!(decode(&message(
+            "crm",
+            0,
+            4,
+            Some(r#"{"event_type":"page_view","source":"web","data":{}}"#)
+        ))
+        .is_err());
+
+        assert!(decode(&message("crm", 0, 1, None)).is_err());
+        assert!(decode(&message("crm", 0, 2, Some("not json"))).is_err());
+        assert!(decode(&message(
+            "crm",
+            0,
+            3,
+            Some(r#"{"tenant_id":"00000000-0000-0000-0000-000000000001","event_type":"","source":"web","data":{}}"#)
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_offsets_to_commit_uses_next_offset_per_partition() {
+        let batch = vec![
+            message("crm", 0, 5, None),
+            message("crm", 0, 7, None),
+            message("crm", 1, 2, None),
+            message("marketing", 0, 10, None),
+        ];
+
+        let offsets = offsets_to_commit(&batch);
+        assert_eq!(offsets.count(), 3);
+        assert_eq!(
+            offsets.find_partition("crm", 0).unwrap().offset(),
+            Offset::Offset(8)
+        );
+        assert_eq!(
+            offsets.find_partition("crm", 1).unwrap().offset(),
+            Offset::Offset(3)
+        );
+        assert_eq!(
+            offsets.find_partition("marketing", 0).unwrap().offset(),
+            Offset::Offset(11)
+        );
+    }
+
+    #[test]
+    fn test_is_permanent() {
+        // untranslatable_character, e.g. \u0000 in jsonb
+        assert!(is_permanent(&database_error("22P05")));
+        assert!(is_permanent(&database_error("23503")));
+        // serialization_failure is worth retrying
+        assert!(!is_permanent(&database_error("40001")));
+        assert!(!is_permanent(&AppError::Database(
+            sqlx::Error::PoolTimedOut
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_insert_isolating_dead_letters_only_bad_rows() {
+        let rows = ["a", "b", "bad", "c", "d", "bad"];
+        let mut attempts = 0;
+        let (stored, rejected) = insert_isolating(rows.len(), |indexes| {
+            attempts += 1;
+            let batch: Vec<&str> = indexes.iter().map(|&index| rows[index]).collect();
+            async move {
+                if batch.contains(&"bad") {
+                    Err(database_error("22P05"))
+                } else {
+                    Ok(batch)
+                }
+            }
+        })
+        .await;
+
+        let mut stored = stored;
+        stored.sort_unstable();
+        assert_eq!(stored, vec!["a", "b", "c", "d"]);
+        let mut rejected: Vec<usize> = rejected.into_iter().map(|(index, _)| index).collect();
+        rejected.sort_unstable();
+        assert_eq!(rejected, vec![2, 5]);
+        assert!(attempts < rows.len() * 2);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{db, error::Result, models::Event, services::identities::IdentityResolver};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// A validated event ready to be written, with its id already assigned so
+/// callers can report it before the insert completes.
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub event_type: String,
+    pub source: String,
+    pub data: serde_json::Value,
+}
+
+/// Stores the event attributed to the identity its data resolves to.
+pub async fn insert_event(
+    pool: &PgPool,
+    resolver: &IdentityResolver,
+    event: NewEvent,
+) -> Result<Event> {
+    let mut tx = db::begin_tenant(pool, event.tenant_id).await?;
+    let event = insert_event_in(&mut tx, resolver, event).await?;
+    tx.commit().await?;
+
+    Ok(event)
+}
+
+/// Stores the event as part of the caller's transaction, for events written
+/// together with the change they describe. `tx` must be scoped to the
+/// event's tenant.
+pub async fn insert_event_in(
+    tx: &mut Transaction<'static, Postgres>,
+    resolver: &IdentityResolver,
+    event: NewEvent,
+) -> Result<Event> {
+    let identity_ids = resolver
+        .resolve_events(tx, event.tenant_id, std::slice::from_ref(&event))
+        .await?;
+    let event = sqlx::query_as::<_, Event>(
+        r#"
+        INSERT INTO events (id, tenant_id, event_type, source, data, identity_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, tenant_id, event_type, source, data, created_at, updated_at
+        "#,
+    )
+    .bind(event.id)
+    .bind(event.tenant_id)
+    .bind(&event.event_type)
+    .bind(&event.source)
+    .bind(&event.data)
+    .bind(identity_ids[0])
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(event)
+}
+
+/// Writes all events in one transaction, with a multi-row `UNNEST` insert
+/// per tenant since row-level security admits one tenant at a time. Events
+/// whose id is already stored are skipped, so replayed deliveries with
+/// stable ids are not duplicated; only newly stored events are returned.
+pub async fn insert_events(
+    pool: &PgPool,
+    resolver: &IdentityResolver,
+    events: Vec<NewEvent>,
+) -> Result<Vec<Event>> {
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut by_tenant: BTreeMap<Uuid, Vec<NewEvent>> = BTreeMap::new();
+    for event in events {
+        by_tenant.entry(event.tenant_id).or_default().push(event);
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut inserted = Vec::new();
+    for (tenant_id, events) in by_tenant {
+        db::set_tenant(&mut tx, tenant_id).await?;
+        let identity_ids = resolver.resolve_events(&mut tx, tenant_id, &events).await?;
+
+        let mut ids = Vec::with_capacity(events.len());
+        let mut event_types = Vec::with_capacity(events.len());
+        let mut sources = Vec::with_capacity(events.len());
+        let mut data = Vec::with_capacity(events.len());
+        for event in events {
+            ids.push(event.id);
+            event_types.push(event.event_type);
+            sources.push(event.source);
+            data.push(event.data);
+        }
+
+        let events = sqlx::query_as::<_, Event>(
+            r#"
+            INSERT INTO events (id, tenant_id, event_type, source, data, identity_id)
+            SELECT id, $1, event_type, source, data, identity_id
+            FROM UNNEST($2::uuid[], $3::varchar[], $4::varchar[], $5::jsonb[], $6::uuid[])
+                AS batch (id, event_type, source, data, identity_id)
+            ON CONFLICT (id) DO NOTHING
+            RETURNING id, tenant_id, event_type, source, data, created_at, updated_at
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(&ids)
+        .bind(&event_types)
+        .bind(&sources)
+        .bind(&data)
+        .bind(&identity_ids)
+        .fetch_all(&mut *tx)
+        .await?;
+        inserted.extend(events);
+    }
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+/// The subset of `tenant_ids` that exist, so callers can reject events for
+/// unknown tenants instead of failing a whole batch on the foreign key.
+pub async fn existing_tenants(pool: &PgPool, tenant_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM tenants WHERE id = ANY($1)")
+        .bind(tenant_ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ids)
+}
@@ -0,0 +1,726 @@
+use crate::{
+    config::IdentityConfig,
+    db,
+    error::Result,
+    models::{Contact, Event, IdentifierKind, Page, PageQuery},
+    services::event_store::NewEvent,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+/// Matches the `VARCHAR(255)` column of the `identifiers` table.
+const MAX_IDENTIFIER_LENGTH: usize = 255;
+
+pub type Identifier = (IdentifierKind, String);
+
+/// Finds identifiers in event data at the paths set in `IdentityConfig`.
+#[derive(Clone)]
+pub struct IdentityResolver {
+    paths: Arc<[(IdentifierKind, Vec<String>)]>,
+}
+
+impl IdentityResolver {
+    pub fn new(config: &IdentityConfig) -> Self {
+        let mut paths = Vec::new();
+        for (kind, kind_paths) in [
+            (IdentifierKind::Email, &config.email),
+            (IdentifierKind::UserId, &config.user_id),
+            (IdentifierKind::AnonymousId, &config.anonymous_id),
+            (IdentifierKind::Phone, &config.phone),
+        ] {
+            for path in kind_paths {
+                let segments = path.split('.').map(|s| s.trim().to_string()).collect();
+                paths.push((kind, segments));
+            }
+        }
+        Self {
+            paths: paths.into(),
+        }
+    }
+
+    /// The normalized identifiers of `data`, sorted and without duplicates.
+    pub fn identifiers(&self, data: &Value) -> Vec<Identifier> {
+        let mut identifiers: Vec<Identifier> = self
+            .paths
+            .iter()
+            .filter_map(|(kind, path)| {
+                let value = path
+                    .iter()
+                    .try_fold(data, |value, segment| value.get(segment))?;
+                Some((*kind, normalize(*kind, value)?))
+            })
+            .collect();
+        identifiers.sort();
+        identifiers.dedup();
+        identifiers
+    }
+
+    /// Resolves the identity of each event, in order, with the outcome of
+    /// resolving them one by one but a fixed number of statements per batch:
+    /// the identities of every identifier are read at once, merges are
+    /// planned in memory and the result is written in bulk. Must run in the
+    /// events' tenant transaction, since merges repoint stored events.
+    pub async fn resolve_events(
+        &self,
+        tx: &mut Transaction<'static, Postgres>,
+        tenant_id: Uuid,
+        events: &[NewEvent],
+    ) -> Result<Vec<Option<Uuid>>> {
+        let identifiers: Vec<Vec<Identifier>> = events
+            .iter()
+            .map(|event| self.identifiers(&event.data))
+            .collect();
+        let mut all: Vec<&Identifier> = identifiers.iter().flatten().collect();
+        if all.is_empty() {
+            return Ok(vec![None; events.len()]);
+        }
+        all.sort();
+        all.dedup();
+        let kinds: Vec<&str> = all.iter().map(|(kind, _)| kind.as_str()).collect();
+        let values: Vec<&str> = all.iter().map(|(_, value)| value.as_str()).collect();
+
+        lock_identities(tx, tenant_id).await?;
+        let stored = sqlx::query_as::<_, StoredIdentifier>(
+            r#"
+            SELECT f.kind, f.value, f.identity_id, i.contact_id, i.created_at
+            FROM identifiers f
+            JOIN identities i ON i.id = f.identity_id
+            WHERE f.tenant_id = $1
+              AND (f.kind, f.value) IN (SELECT * FROM UNNEST($2::text[], $3::varchar[]))
+            "#,
+        )
+        .bind(tenant_id)
+        .bind(&kinds)
+        .bind(&values)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let mut plan = BatchPlan::new(stored);
+        let resolved: Vec<Option<Uuid>> = identifiers
+            .iter()
+            .map(|identifiers| (!identifiers.is_empty()).then(|| plan.resolve(identifiers)))
+            .collect();
+        plan.write(tx, tenant_id).await?;
+
+        Ok(resolved
+            .into_iter()
+            .map(|identity_id| identity_id.map(|identity_id| plan.survivor(identity_id)))
+            .collect())
+    }
+}
+
+/// Strings are trimmed and numbers accepted as written; other JSON values
+/// are not identifiers. Emails are lowercased and phones reduced to their
+/// digits, keeping a leading `+`.
+fn normalize(kind: IdentifierKind, value: &Value) -> Option<String> {
+    let value = match value {
+        Value::String(value) => value.trim().to_string(),
+        Value::Number(number) if kind != IdentifierKind::Email => number.to_string(),
+        _ => return None,
+    };
+
+    let value = match kind {
+        IdentifierKind::Email => value.to_lowercase(),
+        IdentifierKind::Phone => {
+            let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+            if digits.is_empty() {
+                return None;
+            }
+            if value.starts_with('+') {
+                format!("+{}", digits)
+            } else {
+                digits
+            }
+        }
+        IdentifierKind::UserId | IdentifierKind::AnonymousId => value,
+    };
+
+    (!value.is_empty() && value.len() <= MAX_IDENTIFIER_LENGTH).then_some(value)
+}
+
+/// A contact is known by its email and phone, so events carrying either are
+/// attributed to it.
+pub fn contact_identifiers(contact: &Contact) -> Vec<Identifier> {
+    [
+        (IdentifierKind::Email, contact.email.as_deref()),
+        (IdentifierKind::Phone, contact.phone.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(kind, value)| Some((kind, normalize(kind, &Value::from(value?))?)))
+    .collect()
+}
+
+/// Attaches the contact to the identity holding its identifiers, merging in
+/// any unlinked identity they point to.
+pub async fn link_contact(
+    tx: &mut Transaction<'static, Postgres>,
+    contact: &Contact,
+) -> Result<()> {
+    resolve(
+        tx,
+        contact.tenant_id,
+        &contact_identifiers(contact),
+        Some(contact.id),
+    )
+    .await
+}
+
+/// Events of every identity linked to the contact, newest first.
+pub async fn contact_timeline(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    contact_id: Uuid,
+    page: &PageQuery,
+) -> Result<Page<Event>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Event>(
+        r#"
+        SELECT e.id, e.tenant_id, e.event_type, e.source, e.data, e.created_at, e.updated_at
+        FROM events e
+        JOIN identities i ON i.id = e.identity_id
+        WHERE e.tenant_id = $1 AND i.contact_id = $2
+        ORDER BY e.created_at DESC, e.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(contact_id)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM events e
+        JOIN identities i ON i.id = e.identity_id
+        WHERE e.tenant_id = $1 AND i.contact_id = $2
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(contact_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Candidate {
+    id: Uuid,
+    contact_id: Option<Uuid>,
+}
+
+#[derive(Debug, PartialEq)]
+struct MergePlan {
+    survivor: Option<Uuid>,
+    absorbed: Vec<Uuid>,
+    contact_id: Option<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StoredIdentifier {
+    kind: IdentifierKind,
+    value: String,
+    identity_id: Uuid,
+    contact_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct PlannedIdentity {
+    contact_id: Option<Uuid>,
+    /// `None` for identities created by the batch.
+    created_at: Option<DateTime<Utc>>,
+}
+
+/// Replays `resolve` for a batch of events in memory, starting from the
+/// stored identities of the batch's identifiers.
+#[derive(Debug, Default)]
+struct BatchPlan {
+    identities: HashMap<Uuid, PlannedIdentity>,
+    owners: HashMap<Identifier, Uuid>,
+    /// Identifiers not stored yet, in the order they were first seen.
+    new_identifiers: Vec<Identifier>,
+    /// Each absorbed identity and the one it was merged into, which may
+    /// itself have been absorbed later in the batch.
+    merged_into: HashMap<Uuid, Uuid>,
+    /// Absorbed identities that are stored and must be merged in the
+    /// database.
+    absorbed_stored: Vec<Uuid>,
+    /// The identity each event resolved to when it was resolved.
+    resolved: Vec<Uuid>,
+}
+
+impl BatchPlan {
+    fn new(stored: Vec<StoredIdentifier>) -> Self {
+        let mut plan = Self::default();
+        for row in stored {
+            plan.identities
+                .entry(row.identity_id)
+                .or_insert(PlannedIdentity {
+                    contact_id: row.contact_id,
+                    created_at: Some(row.created_at),
+                });
+            plan.owners.insert((row.kind, row.value), row.identity_id);
+        }
+        plan
+    }
+
+    /// Resolves one event like `resolve` would. Candidates are ordered as
+    /// the database orders them: stored identities oldest first, then those
+    /// created in this transaction, which share its timestamp, by id.
+    fn resolve(&mut self, identifiers: &[Identifier]) -> Uuid {
+        let mut ids: Vec<Uuid> = identifiers
+            .iter()
+            .filter_map(|identifier| self.owners.get(identifier).copied())
+            .collect();
+        ids.sort_by_key(|id| {
+            let created_at = self.identities[id].created_at;
+            (created_at.is_none(), created_at, *id)
+        });
+        ids.dedup();
+        let candidates: Vec<Candidate> = ids
+            .into_iter()
+            .map(|id| Candidate {
+                id,
+                contact_id: self.identities[&id].contact_id,
+            })
+            .collect();
+
+        let plan = plan_merge(&candidates, None);
+        let identity_id = plan.survivor.unwrap_or_else(|| {
+            let id = Uuid::new_v4();
+            self.identities.insert(
+                id,
+                PlannedIdentity {
+                    contact_id: None,
+                    created_at: None,
+                },
+            );
+            id
+        });
+
+        if !plan.absorbed.is_empty() {
+            for absorbed in &plan.absorbed {
+                if let Some(identity) = self.identities.remove(absorbed) {
+                    if identity.created_at.is_some() {
+                        self.absorbed_stored.push(*absorbed);
+                    }
+                }
+                self.merged_into.insert(*absorbed, identity_id);
+            }
+            for owner in self.owners.values_mut() {
+                if plan.absorbed.contains(owner) {
+                    *owner = identity_id;
+                }
+            }
+        }
+
+        for identifier in identifiers {
+            if !self.owners.contains_key(identifier) {
+                self.owners.insert(identifier.clone(), identity_id);
+                self.new_identifiers.push(identifier.clone());
+            }
+        }
+        self.resolved.push(identity_id);
+        identity_id
+    }
+
+    /// The identity `identity_id` ended up merged into, or itself.
+    fn survivor(&self, mut identity_id: Uuid) -> Uuid {
+        while let Some(&into) = self.merged_into.get(&identity_id) {
+            identity_id = into;
+        }
+        identity_id
+    }
+
+    async fn write(&self, tx: &mut Transaction<'static, Postgres>, tenant_id: Uuid) -> Result<()> {
+        let created: Vec<Uuid> = self
+            .identities
+            .iter()
+            .filter(|(_, identity)| identity.created_at.is_none())
+            .map(|(id, _)| *id)
+            .collect();
+        if !created.is_empty() {
+            sqlx::query(
+                "INSERT INTO identities (id, tenant_id) SELECT id, $1 FROM UNNEST($2::uuid[]) AS new (id)",
+            )
+            .bind(tenant_id)
+            .bind(&created)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        if !self.absorbed_stored.is_empty() {
+            let survivors: Vec<Uuid> = self
+                .absorbed_stored
+                .iter()
+                .map(|&absorbed| self.survivor(absorbed))
+                .collect();
+            sqlx::query(
+                r#"
+                UPDATE identifiers SET identity_id = merge.survivor
+                FROM UNNEST($1::uuid[], $2::uuid[]) AS merge (absorbed, survivor)
+                WHERE identifiers.identity_id = merge.absorbed
+                "#,
+            )
+            .bind(&self.absorbed_stored)
+            .bind(&survivors)
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE events SET identity_id = merge.survivor
+                FROM UNNEST($2::uuid[], $3::uuid[]) AS merge (absorbed, survivor)
+                WHERE events.tenant_id = $1 AND events.identity_id = merge.absorbed
+                "#,
+            )
+            .bind(tenant_id)
+            .bind(&self.absorbed_stored)
+            .bind(&survivors)
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query("DELETE FROM identities WHERE id = ANY($1)")
+                .bind(&self.absorbed_stored)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        let mut touched: Vec<Uuid> = self
+            .resolved
+            .iter()
+            .map(|&identity_id| self.survivor(identity_id))
+            .filter(|id| self.identities[id].created_at.is_some())
+            .collect();
+        touched.sort();
+        touched.dedup();
+        if !touched.is_empty() {
+            sqlx::query("UPDATE identities SET updated_at = NOW() WHERE id = ANY($1)")
+                .bind(&touched)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        if !self.new_identifiers.is_empty() {
+            let kinds: Vec<&str> = self
+                .new_identifiers
+                .iter()
+                .map(|(kind, _)| kind.as_str())
+                .collect();
+            let values: Vec<&str> = self
+                .new_identifiers
+                .iter()
+                .map(|(_, value)| value.as_str())
+                .collect();
+            let owners: Vec<Uuid> = self
+                .new_identifiers
+                .iter()
+                .map(|identifier| self.survivor(self.owners[identifier]))
+                .collect();
+            sqlx::query(
+                r#"
+                INSERT INTO identifiers (tenant_id, kind, value, identity_id)
+                SELECT $1, kind, value, identity_id
+                FROM UNNEST($2::text[], $3::varchar[], $4::uuid[]) AS new (kind, value, identity_id)
+                ON CONFLICT (tenant_id, kind, value) DO NOTHING
+                "#,
+            )
+            .bind(tenant_id)
+            .bind(&kinds)
+            .bind(&values)
+            .bind(&owners)
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes resolution per tenant, so two transactions cannot each create
+/// an identity for the same new identifier.
+async fn lock_identities(tx: &mut Transaction<'static, Postgres>, tenant_id: Uuid) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('identities:' || $1::text))")
+        .bind(tenant_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Decides how the identities matched by one event or contact are merged,
+/// given `candidates` oldest first. Only unlinked identities are absorbed:
+/// identities of different contacts are left for duplicate review, and
+/// those a contact gained from a merge stay apart so the merge can be
+/// undone.
+fn plan_merge(candidates: &[Candidate], contact_id: Option<Uuid>) -> MergePlan {
+    let owner = contact_id.or_else(|| candidates.iter().find_map(|c| c.contact_id));
+    let survivor = candidates
+        .iter()
+        .find(|c| owner.is_some() && c.contact_id == owner)
+        .or_else(|| candidates.iter().find(|c| c.contact_id.is_none()))
+        .map(|c| c.id);
+
+    MergePlan {
+        survivor,
+        absorbed: candidates
+            .iter()
+            .filter(|c| c.contact_id.is_none() && Some(c.id) != survivor)
+            .map(|c| c.id)
+            .collect(),
+        contact_id: owner,
+    }
+}
+
+/// Finds or creates the identity for `identifiers`, merging the identities
+/// they already point to.
+async fn resolve(
+    tx: &mut Transaction<'static, Postgres>,
+    tenant_id: Uuid,
+    identifiers: &[Identifier],
+    contact_id: Option<Uuid>,
+) -> Result<()> {
+    let kinds: Vec<&str> = identifiers.iter().map(|(kind, _)| kind.as_str()).collect();
+    let values: Vec<&str> = identifiers
+        .iter()
+        .map(|(_, value)| value.as_str())
+        .collect();
+
+    lock_identities(tx, tenant_id).await?;
+
+    let candidates = sqlx::query_as::<_, Candidate>(
+        r#"
+        SELECT id, contact_id FROM identities
+        WHERE tenant_id = $1
+          AND (
+            id IN (
+                SELECT identity_id FROM identifiers
+                WHERE tenant_id = $1
+                  AND (kind, value) IN (SELECT * FROM UNNEST($2::text[], $3::varchar[]))
+            )
+            OR contact_id = $4
+          )
+        ORDER BY created_at, id
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(&kinds)
+    .bind(&values)
+    .bind(contact_id)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let plan = plan_merge(&candidates, contact_id);
+    let identity_id = match plan.survivor {
+        Some(identity_id) => identity_id,
+        None => {
+            sqlx::query_scalar::<_, Uuid>(
+                "INSERT INTO identities (id, tenant_id) VALUES ($1, $2) RETURNING id",
+            )
+            .bind(Uuid::new_v4())
+            .bind(tenant_id)
+            .fetch_one(&mut **tx)
+            .await?
+        }
+    };
+
+    if !plan.absorbed.is_empty() {
+        sqlx::query("UPDATE identifiers SET identity_id = $1 WHERE identity_id = ANY($2)")
+            .bind(identity_id)
+            .bind(&plan.absorbed)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query(
+            "UPDATE events SET identity_id = $1 WHERE tenant_id = $2 AND identity_id = ANY($3)",
+        )
+        .bind(identity_id)
+        .bind(tenant_id)
+        .bind(&plan.absorbed)
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query("DELETE FROM identities WHERE id = ANY($1)")
+            .bind(&plan.absorbed)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE identities
+        SET contact_id = COALESCE(contact_id, $2), updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(identity_id)
+    .bind(plan.contact_id)
+    .execute(&mut **tx)
+    .await?;
+
+    // Identifiers held by an identity of another contact stay with it
+    sqlx::query(
+        r#"
+        INSERT INTO identifiers (tenant_id, kind, value, identity_id)
+        SELECT $1, kind, value, $4
+        FROM UNNEST($2::text[], $3::varchar[]) AS new (kind, value)
+        ON CONFLICT (tenant_id, kind, value) DO NOTHING
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(&kinds)
+    .bind(&values)
+    .bind(identity_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers_follow_configured_paths() {
+        let resolver = IdentityResolver::new(&IdentityConfig::default());
+        let data = serde_json::json!({
+            "page": "/home",
+            "user_id": 123,
+            "anonymousId": " a-1 ",
+            "traits": { "email": "Jane@Example.com", "phone": "+1 (555) 010-9999" },
+            "email": "jane@example.com",
+            "phone": ["not", "an", "identifier"],
+        });
+
+        assert_eq!(
+            resolver.identifiers(&data),
+            vec![
+                (IdentifierKind::Email, "jane@example.com".to_string()),
+                (IdentifierKind::UserId, "123".to_string()),
+                (IdentifierKind::AnonymousId, "a-1".to_string()),
+                (IdentifierKind::Phone, "+15550109999".to_string()),
+            ]
+        );
+        assert!(resolver
+            .identifiers(&serde_json::json!({ "email": 42, "user_id": "  " }))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_plan_merge_keeps_contacts_apart() {
+        let [a, b, c, x, y] = [1, 2, 3, 4, 5].map(Uuid::from_u128);
+        let candidate = |id, contact_id| Candidate { id, contact_id };
+
+        // An event tying two anonymous identities merges them into the oldest
+        assert_eq!(
+            plan_merge(&[candidate(a, None), candidate(b, None)], None),
+            MergePlan {
+                survivor: Some(a),
+                absorbed: vec![b],
+                contact_id: None,
+            }
+        );
+
+        // A linked identity survives, and one of another contact is left alone
+        assert_eq!(
+            plan_merge(
+                &[
+                    candidate(a, None),
+                    candidate(b, Some(x)),
+                    candidate(c, Some(y))
+                ],
+                None
+            ),
+            MergePlan {
+                survivor: Some(b),
+                absorbed: vec![a],
+                contact_id: Some(x),
+            }
+        );
+
+        // Identities a contact gained from a merge are not merged together
+        assert_eq!(
+            plan_merge(
+                &[
+                    candidate(a, Some(x)),
+                    candidate(b, None),
+                    candidate(c, Some(x))
+                ],
+                None
+            ),
+            MergePlan {
+                survivor: Some(a),
+                absorbed: vec![b],
+                contact_id: Some(x),
+            }
+        );
+
+        // A new contact takes over an anonymous identity
+        assert_eq!(
+            plan_merge(&[candidate(a, None), candidate(b, Some(x))], Some(y)),
+            MergePlan {
+                survivor: Some(a),
+                absorbed: vec![],
+                contact_id: Some(y),
+            }
+        );
+
+        assert_eq!(
+            plan_merge(&[], None),
+            MergePlan {
+                survivor: None,
+                absorbed: vec![],
+                contact_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_batch_plan_replays_merges_in_order() {
+        let [a, b, x] = [1, 2, 3].map(Uuid::from_u128);
+        let identifier = |kind, value: &str| (kind, value.to_string());
+        let email = identifier(IdentifierKind::Email, "jane@example.com");
+        let phone = identifier(IdentifierKind::Phone, "+15550109999");
+        let anonymous = identifier(IdentifierKind::AnonymousId, "a-1");
+        let stored = |kind, value: &str, identity_id, contact_id, age| StoredIdentifier {
+            kind,
+            value: value.to_string(),
+            identity_id,
+            contact_id,
+            created_at: DateTime::<Utc>::from_timestamp(age, 0).unwrap(),
+        };
+
+        // `a` is anonymous and older than `b`, the identity of contact `x`
+        let mut plan = BatchPlan::new(vec![
+            stored(IdentifierKind::Email, "jane@example.com", a, None, 1),
+            stored(IdentifierKind::Phone, "+15550109999", b, Some(x), 2),
+        ]);
+
+        // A new visitor, then the visitor signing in, then the email and
+        // phone seen together
+        let visitor = plan.resolve(std::slice::from_ref(&anonymous));
+        assert!(![a, b].contains(&visitor));
+        assert_eq!(plan.resolve(&[email.clone(), anonymous.clone()]), a);
+        assert_eq!(plan.resolve(&[email.clone(), phone]), b);
+
+        for identity_id in [visitor, a, b] {
+            assert_eq!(plan.survivor(identity_id), b);
+        }
+        assert_eq!(plan.absorbed_stored, vec![a]);
+        assert_eq!(plan.new_identifiers, vec![anonymous.clone()]);
+        assert_eq!(plan.survivor(plan.owners[&anonymous]), b);
+        assert_eq!(plan.owners[&email], b);
+        assert!(plan
+            .identities
+            .values()
+            .all(|identity| identity.created_at.is_some()));
+    }
+}
@@ -0,0 +1,16 @@
+pub mod accounts;
+pub mod activities;
+pub mod analytics_cache;
+pub mod api_keys;
+pub mod auth;
+pub mod contact_merges;
+pub mod contacts;
+pub mod deals;
+pub mod duplicates;
+pub mod event_consumer;
+pub mod event_publisher;
+pub mod event_store;
+pub mod identities;
+pub mod pipelines;
+pub mod sessions;
+pub mod tasks;
@@ -0,0 +1,286 @@
+use crate::{
+    db,
+    error::{AppError, Result},
+    models::{Pipeline, PipelineDetail, PipelineRequest, Stage},
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const PIPELINE_COLUMNS: &str = "id, tenant_id, name, created_at, updated_at";
+
+pub const STAGE_COLUMNS: &str = "id, tenant_id, pipeline_id, name, position, kind, probability, \
+     created_at, updated_at";
+
+/// Expects a request already checked by `api::pipelines::validate_pipeline`.
+pub async fn create_pipeline(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &PipelineRequest,
+) -> Result<PipelineDetail> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let pipeline = sqlx::query_as::<_, Pipeline>(&format!(
+        "INSERT INTO pipelines (id, tenant_id, name) VALUES ($1, $2, $3) RETURNING {}",
+        PIPELINE_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(request.name.trim())
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let stages = save_stages(&mut tx, &pipeline, request).await?;
+    tx.commit().await?;
+
+    Ok(PipelineDetail { pipeline, stages })
+}
+
+/// Every pipeline of the tenant with its stages. Tenants keep a handful, so
+/// the list is not paginated.
+pub async fn list_pipelines(pool: &PgPool, tenant_id: Uuid) -> Result<Vec<PipelineDetail>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let pipelines = sqlx::query_as::<_, Pipeline>(&format!(
+        "SELECT {} FROM pipelines WHERE tenant_id = $1 ORDER BY created_at, id",
+        PIPELINE_COLUMNS
+    ))
+    .bind(tenant_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let stages = sqlx::query_as::<_, Stage>(&format!(
+        "SELECT {} FROM stages WHERE tenant_id = $1 ORDER BY position",
+        STAGE_COLUMNS
+    ))
+    .bind(tenant_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let mut by_pipeline: HashMap<Uuid, Vec<Stage>> = HashMap::new();
+    for stage in stages {
+        by_pipeline
+            .entry(stage.pipeline_id)
+            .or_default()
+            .push(stage);
+    }
+    Ok(pipelines
+        .into_iter()
+        .map(|pipeline| PipelineDetail {
+            stages: by_pipeline.remove(&pipeline.id).unwrap_or_default(),
+            pipeline,
+        })
+        .collect())
+}
+
+/// Pipelines of other tenants are reported as missing.
+pub async fn get_pipeline(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<PipelineDetail> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let pipeline = sqlx::query_as::<_, Pipeline>(&format!(
+        "SELECT {} FROM pipelines WHERE id = $1 AND tenant_id = $2",
+        PIPELINE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| not_found(id))?;
+
+    let stages = sqlx::query_as::<_, Stage>(&format!(
+        "SELECT {} FROM stages WHERE pipeline_id = $1 ORDER BY position",
+        STAGE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(PipelineDetail { pipeline, stages })
+}
+
+/// Renames the pipeline and replaces its stages. Removing a stage that
+/// still holds deals is a conflict; move the deals first.
+pub async fn update_pipeline(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &PipelineRequest,
+) -> Result<PipelineDetail> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let pipeline = sqlx::query_as::<_, Pipeline>(&format!(
+        r#"
+        UPDATE pipelines SET name = $3, updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        PIPELINE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.name.trim())
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| not_found(id))?;
+
+    let stages = save_stages(&mut tx, &pipeline, request).await?;
+    tx.commit().await?;
+
+    Ok(PipelineDetail { pipeline, stages })
+}
+
+/// Pipelines with deals cannot be deleted; their stages go with them.
+pub async fn delete_pipeline(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let pipeline_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pipelines WHERE id = $1 AND tenant_id = $2 FOR UPDATE)",
+    )
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !pipeline_exists {
+        return Err(not_found(id));
+    }
+
+    // Deals inserted into a stage hold a key share lock on it, so locking the
+    // stages waits for those in flight and blocks new ones until the delete
+    // commits; the count below then sees every deal.
+    sqlx::query("SELECT id FROM stages WHERE pipeline_id = $1 FOR UPDATE")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    let deals: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deals WHERE pipeline_id = $1")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if deals > 0 {
+        return Err(AppError::Conflict(format!(
+            "Pipeline {} still has {} deals",
+            id, deals
+        )));
+    }
+
+    sqlx::query("DELETE FROM pipelines WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Other tenants' stages, and stages of another pipeline, are reported as
+/// missing.
+pub async fn get_stage(
+    tx: &mut Transaction<'static, Postgres>,
+    tenant_id: Uuid,
+    pipeline_id: Uuid,
+    id: Uuid,
+) -> Result<Stage> {
+    sqlx::query_as::<_, Stage>(&format!(
+        "SELECT {} FROM stages WHERE id = $1 AND tenant_id = $2 AND pipeline_id = $3",
+        STAGE_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(pipeline_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::Validation(format!("Stage {} is not in pipeline {}", id, pipeline_id)))
+}
+
+/// The stage new deals of the pipeline start in.
+pub async fn first_stage(
+    tx: &mut Transaction<'static, Postgres>,
+    tenant_id: Uuid,
+    pipeline_id: Uuid,
+) -> Result<Stage> {
+    sqlx::query_as::<_, Stage>(&format!(
+        r#"
+        SELECT {} FROM stages
+        WHERE pipeline_id = $1 AND tenant_id = $2
+        ORDER BY position
+        LIMIT 1
+        "#,
+        STAGE_COLUMNS
+    ))
+    .bind(pipeline_id)
+    .bind(tenant_id)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::Validation(format!("Pipeline {} not found", pipeline_id)))
+}
+
+/// Brings the pipeline's stages in line with `request.stages`, in order.
+async fn save_stages(
+    tx: &mut Transaction<'static, Postgres>,
+    pipeline: &Pipeline,
+    request: &PipelineRequest,
+) -> Result<Vec<Stage>> {
+    let existing: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM stages WHERE pipeline_id = $1")
+        .bind(pipeline.id)
+        .fetch_all(&mut **tx)
+        .await?;
+    let kept: HashSet<Uuid> = request.stages.iter().filter_map(|stage| stage.id).collect();
+    if let Some(id) = kept.iter().find(|id| !existing.contains(id)) {
+        return Err(AppError::Validation(format!(
+            "Stage {} is not in pipeline {}",
+            id, pipeline.id
+        )));
+    }
+
+    let removed: Vec<Uuid> = existing
+        .into_iter()
+        .filter(|id| !kept.contains(id))
+        .collect();
+    if !removed.is_empty() {
+        // Locked before counting, as in `delete_pipeline`
+        sqlx::query("SELECT id FROM stages WHERE id = ANY($1) FOR UPDATE")
+            .bind(&removed)
+            .execute(&mut **tx)
+            .await?;
+        let deals: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM deals WHERE stage_id = ANY($1)")
+            .bind(&removed)
+            .fetch_one(&mut **tx)
+            .await?;
+        if deals > 0 {
+            return Err(AppError::Conflict(format!(
+                "Removed stages still have {} deals",
+                deals
+            )));
+        }
+        sqlx::query("DELETE FROM stages WHERE id = ANY($1)")
+            .bind(&removed)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    let mut stages = Vec::with_capacity(request.stages.len());
+    for (position, stage) in request.stages.iter().enumerate() {
+        let stage = sqlx::query_as::<_, Stage>(&format!(
+            r#"
+            INSERT INTO stages (id, tenant_id, pipeline_id, name, position, kind, probability)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE
+            SET name = EXCLUDED.name, position = EXCLUDED.position, kind = EXCLUDED.kind,
+                probability = EXCLUDED.probability, updated_at = NOW()
+            RETURNING {}
+            "#,
+            STAGE_COLUMNS
+        ))
+        .bind(stage.id.unwrap_or_else(Uuid::new_v4))
+        .bind(pipeline.tenant_id)
+        .bind(pipeline.id)
+        .bind(stage.name.trim())
+        .bind(position as i32)
+        .bind(stage.kind)
+        .bind(stage.probability)
+        .fetch_one(&mut **tx)
+        .await?;
+        stages.push(stage);
+    }
+    Ok(stages)
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Pipeline {} not found", id))
+}
@@ -0,0 +1,232 @@
+use crate::{
+    config::AuthConfig,
+    error::{AppError, Result},
+};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+const REFRESH_PREFIX: &str = "auth:v1:refresh";
+
+/// Refresh tokens that have already been exchanged, kept so that replaying
+/// one can be told apart from presenting a token that never existed.
+const ROTATED_PREFIX: &str = "auth:v1:rotated";
+
+const REVOKED_PREFIX: &str = "auth:v1:revoked";
+
+/// Moves a refresh token to the rotated set and returns its session, or nil
+/// if the token is not (or no longer) active. Atomic so a token can only be
+/// exchanged once even under concurrent refreshes.
+const ROTATE_SCRIPT: &str = r#"
+local session = redis.call('GET', KEYS[1])
+if session then
+    redis.call('DEL', KEYS[1])
+    redis.call('SET', KEYS[2], session, 'EX', ARGV[1])
+end
+return session
+"#;
+
+/// The login session a refresh token belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub user_id: Uuid,
+    pub session_id: Uuid,
+}
+
+/// Refresh tokens and the session revocation list, stored in Redis.
+///
+/// Unlike the analytics cache this fails closed: if Redis is unavailable,
+/// requests are rejected rather than treated as unrevoked. The connection is
+/// opened on first use.
+#[derive(Clone)]
+pub struct SessionStore {
+    client: redis::Client,
+    connection: Arc<OnceCell<ConnectionManager>>,
+    refresh_ttl_seconds: u64,
+}
+
+impl SessionStore {
+    pub fn new(client: redis::Client, config: &AuthConfig) -> Self {
+        Self {
+            client,
+            connection: Arc::new(OnceCell::new()),
+            refresh_ttl_seconds: config.refresh_token_ttl_seconds.max(0) as u64,
+        }
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?;
+        Ok(connection.clone())
+    }
+
+    /// Starts a new session for the user and returns its first refresh token.
+    pub async fn start(&self, user_id: Uuid) -> Result<(Session, String)> {
+        let session = Session {
+            user_id,
+            session_id: Uuid::new_v4(),
+        };
+        let refresh_token = self.issue_refresh_token(session).await?;
+        Ok((session, refresh_token))
+    }
+
+    /// Exchanges a refresh token for a new one in the same session. Each
+    /// token works once; presenting a spent token again means it was copied,
+    /// so the whole session is revoked.
+    pub async fn rotate(&self, refresh_token: &str) -> Result<(Session, String)> {
+        let hash = hash_token(refresh_token);
+        let mut connection = self.connection().await?;
+
+        let active: Option<String> = redis::Script::new(ROTATE_SCRIPT)
+            .key(refresh_key(&hash))
+            .key(rotated_key(&hash))
+            .arg(self.refresh_ttl_seconds)
+            .invoke_async(&mut connection)
+            .await?;
+
+        let session = match active {
+            Some(json) => decode_session(&json)?,
+            None => {
+                let spent: Option<String> = connection.get(rotated_key(&hash)).await?;
+                if let Some(json) = spent {
+                    let session = decode_session(&json)?;
+                    tracing::warn!(
+                        user_id = %session.user_id,
+                        session_id = %session.session_id,
+                        "refresh token reused, revoking session"
+                    );
+                    self.revoke(session.session_id).await?;
+                }
+                return Err(AppError::Auth("Invalid refresh token".to_string()));
+            }
+        };
+
+        if self.is_revoked(session.session_id).await? {
+            return Err(AppError::Auth("Session has been revoked".to_string()));
+        }
+
+        let refresh_token = self.issue_refresh_token(session).await?;
+        Ok((session, refresh_token))
+    }
+
+    /// Ends the session a refresh token belongs to. Unknown tokens are
+    /// ignored so logging out twice is harmless.
+    pub async fn end(&self, refresh_token: &str) -> Result<()> {
+        let key = refresh_key(&hash_token(refresh_token));
+        let mut connection = self.connection().await?;
+
+        let (active,): (Option<String>,) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .del(&key)
+            .ignore()
+            .query_async(&mut connection)
+            .await?;
+
+        if let Some(json) = active {
+            self.revoke(decode_session(&json)?.session_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Immediately invalidates every access and refresh token issued for the
+    /// session. The marker outlives any token the session could have issued.
+    pub async fn revoke(&self, session_id: Uuid) -> Result<()> {
+        let mut connection = self.connection().await?;
+        connection
+            .set_ex::<_, _, ()>(revoked_key(session_id), 1, self.refresh_ttl_seconds)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn is_revoked(&self, session_id: Uuid) -> Result<bool> {
+        let mut connection = self.connection().await?;
+        Ok(connection.exists(revoked_key(session_id)).await?)
+    }
+
+    async fn issue_refresh_token(&self, session: Session) -> Result<String> {
+        let refresh_token = generate_token();
+        let json = serde_json::to_string(&session)
+            .map_err(|e| AppError::Internal(format!("Failed to encode session: {}", e)))?;
+
+        let mut connection = self.connection().await?;
+        connection
+            .set_ex::<_, _, ()>(
+                refresh_key(&hash_token(&refresh_token)),
+                json,
+                self.refresh_ttl_seconds,
+            )
+            .await?;
+        Ok(refresh_token)
+    }
+}
+
+fn generate_token() -> String {
+    // Two v4 UUIDs give 244 bits from the OS random source
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Only hashes are stored, so a Redis dump does not leak usable tokens.
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha3_256::digest(token.as_bytes()))
+}
+
+fn refresh_key(hash: &str) -> String {
+    format!("{}:{}", REFRESH_PREFIX, hash)
+}
+
+fn rotated_key(hash: &str) -> String {
+    format!("{}:{}", ROTATED_PREFIX, hash)
+}
+
+fn revoked_key(session_id: Uuid) -> String {
+    format!("{}:{}", REVOKED_PREFIX, session_id)
+}
+
+fn decode_session(json: &str) -> Result<Session> {
+    serde_json::from_str(json)
+        .map_err(|e| AppError::Internal(format!("Corrupt session record: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token_is_random_hex() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn test_keys_never_contain_the_token() {
+        let token = generate_token();
+        let hash = hash_token(&token);
+
+        assert!(!refresh_key(&hash).contains(&token));
+        assert!(refresh_key(&hash).starts_with(REFRESH_PREFIX));
+        assert!(rotated_key(&hash).starts_with(ROTATED_PREFIX));
+        assert_ne!(refresh_key(&hash), rotated_key(&hash));
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let session = Session {
+            user_id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(decode_session(&json).unwrap(), session);
+        assert!(matches!(
+            decode_session("not json"),
+            Err(AppError::Internal(_))
+        ));
+    }
+}
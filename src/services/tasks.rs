@@ -0,0 +1,483 @@
+use crate::{
+    config::TasksConfig,
+    db,
+    error::{AppError, Result},
+    metrics,
+    models::{CreateTaskRequest, Page, PageQuery, RecordLink, Task, TaskFilter, TaskRequest},
+    services::{accounts::trimmed, activities},
+};
+use chrono::Utc;
+use futures::future::join_all;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+const TASK_COLUMNS: &str = "id, tenant_id, title, description, due_at, assignee_id, status, \
+     completed_at, overdue_notified_at, contact_id, account_id, deal_id, created_by, created_at, \
+     updated_at";
+
+/// Overdue tasks notified per transaction; a backlog is drained in batches.
+const NOTIFY_BATCH_SIZE: i64 = 500;
+
+/// How long a notification may wait in the producer queue when it is full.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a claimed batch is left to its notifier before another instance
+/// may take it over. Longer than librdkafka's default delivery timeout of
+/// five minutes, so a slow delivery is not published twice.
+const CLAIM_LEASE: Duration = Duration::from_secs(10 * 60);
+
+/// Expects a request already checked by `api::tasks::validate_task`. The
+/// task is assigned to its creator unless the request names someone else.
+pub async fn create_task(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    request: &CreateTaskRequest,
+    created_by: Uuid,
+) -> Result<Task> {
+    activities::ensure_record(pool, tenant_id, &request.record).await?;
+    let assignee_id = request.task.assignee_id.unwrap_or(created_by);
+    ensure_assignee(pool, tenant_id, assignee_id).await?;
+
+    let task = &request.task;
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let task = sqlx::query_as::<_, Task>(&format!(
+        r#"
+        INSERT INTO tasks
+            (id, tenant_id, title, description, due_at, assignee_id, status, completed_at,
+             contact_id, account_id, deal_id, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7,
+            CASE WHEN $7 = 'completed' THEN NOW() END, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        TASK_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant_id)
+    .bind(task.title.trim())
+    .bind(trimmed(task.description.as_deref()))
+    .bind(task.due_at)
+    .bind(assignee_id)
+    .bind(task.status)
+    .bind(request.record.contact_id)
+    .bind(request.record.account_id)
+    .bind(request.record.deal_id)
+    .bind(created_by)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(task)
+}
+
+/// The tenant's tasks, soonest due first, narrowed to the records in
+/// `record` and by `filter`.
+pub async fn list_tasks(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    record: &RecordLink,
+    filter: &TaskFilter,
+    page: &PageQuery,
+) -> Result<Page<Task>> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Task>(&format!(
+        r#"
+        SELECT {} FROM tasks
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR contact_id = $2)
+          AND ($3::uuid IS NULL OR account_id = $3)
+          AND ($4::uuid IS NULL OR deal_id = $4)
+          AND ($5::uuid IS NULL OR assignee_id = $5)
+          AND ($6::text IS NULL OR status = $6)
+        ORDER BY due_at, id
+        LIMIT $7 OFFSET $8
+        "#,
+        TASK_COLUMNS
+    ))
+    .bind(tenant_id)
+    .bind(record.contact_id)
+    .bind(record.account_id)
+    .bind(record.deal_id)
+    .bind(filter.assignee_id)
+    .bind(filter.status)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM tasks
+        WHERE tenant_id = $1
+          AND ($2::uuid IS NULL OR contact_id = $2)
+          AND ($3::uuid IS NULL OR account_id = $3)
+          AND ($4::uuid IS NULL OR deal_id = $4)
+          AND ($5::uuid IS NULL OR assignee_id = $5)
+          AND ($6::text IS NULL OR status = $6)
+        "#,
+    )
+    .bind(tenant_id)
+    .bind(record.contact_id)
+    .bind(record.account_id)
+    .bind(record.deal_id)
+    .bind(filter.assignee_id)
+    .bind(filter.status)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Open tasks assigned to `assignee_id` that are due within `within_days`,
+/// overdue ones included, soonest due first.
+pub async fn upcoming_tasks(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    assignee_id: Uuid,
+    within_days: u32,
+    page: &PageQuery,
+) -> Result<Page<Task>> {
+    let due_before = Utc::now() + chrono::Duration::days(i64::from(within_days));
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let items = sqlx::query_as::<_, Task>(&format!(
+        r#"
+        SELECT {} FROM tasks
+        WHERE assignee_id = $1 AND tenant_id = $2 AND status = 'open' AND due_at < $3
+        ORDER BY due_at, id
+        LIMIT $4 OFFSET $5
+        "#,
+        TASK_COLUMNS
+    ))
+    .bind(assignee_id)
+    .bind(tenant_id)
+    .bind(due_before)
+    .bind(i64::from(page.per_page()))
+    .bind(page.offset())
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM tasks
+        WHERE assignee_id = $1 AND tenant_id = $2 AND status = 'open' AND due_at < $3
+        "#,
+    )
+    .bind(assignee_id)
+    .bind(tenant_id)
+    .bind(due_before)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Page {
+        items,
+        page: page.page(),
+        per_page: page.per_page(),
+        total,
+    })
+}
+
+/// Tasks of other tenants are reported as missing.
+pub async fn get_task(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<Task> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let task = sqlx::query_as::<_, Task>(&format!(
+        "SELECT {} FROM tasks WHERE id = $1 AND tenant_id = $2",
+        TASK_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    task.ok_or_else(|| not_found(id))
+}
+
+/// Replaces the task's fields; an omitted assignee keeps the current one.
+/// Rescheduling or reopening a task lets it be notified as overdue again.
+pub async fn update_task(
+    pool: &PgPool,
+    tenant_id: Uuid,
+    id: Uuid,
+    request: &TaskRequest,
+) -> Result<Task> {
+    if let Some(assignee_id) = request.assignee_id {
+        ensure_assignee(pool, tenant_id, assignee_id).await?;
+    }
+
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let task = sqlx::query_as::<_, Task>(&format!(
+        r#"
+        UPDATE tasks
+        SET title = $3, description = $4, due_at = $5,
+            assignee_id = COALESCE($6, assignee_id), status = $7,
+            completed_at = CASE WHEN $7 = 'completed' THEN COALESCE(completed_at, NOW()) END,
+            overdue_notified_at = CASE
+                WHEN due_at <> $5 OR (status = 'completed' AND $7 = 'open') THEN NULL
+                ELSE overdue_notified_at
+            END,
+            overdue_claimed_at = CASE
+                WHEN due_at <> $5 OR (status = 'completed' AND $7 = 'open') THEN NULL
+                ELSE overdue_claimed_at
+            END,
+            updated_at = NOW()
+        WHERE id = $1 AND tenant_id = $2
+        RETURNING {}
+        "#,
+        TASK_COLUMNS
+    ))
+    .bind(id)
+    .bind(tenant_id)
+    .bind(request.title.trim())
+    .bind(trimmed(request.description.as_deref()))
+    .bind(request.due_at)
+    .bind(request.assignee_id)
+    .bind(request.status)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    task.ok_or_else(|| not_found(id))
+}
+
+pub async fn delete_task(pool: &PgPool, tenant_id: Uuid, id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let deleted = sqlx::query("DELETE FROM tasks WHERE id = $1 AND tenant_id = $2")
+        .bind(id)
+        .bind(tenant_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+
+    if deleted == 0 {
+        return Err(not_found(id));
+    }
+    Ok(())
+}
+
+/// Periodically publishes a notification for every open task that has
+/// passed its due date, once per task. Records are keyed by tenant and
+/// assignee, so a consumer sees each user's notifications in order.
+pub struct OverdueTaskNotifier {
+    pool: PgPool,
+    producer: FutureProducer,
+    topic: String,
+    interval: Duration,
+}
+
+impl OverdueTaskNotifier {
+    pub fn new(
+        pool: PgPool,
+        producer: FutureProducer,
+        topic: String,
+        config: &TasksConfig,
+    ) -> Self {
+        Self {
+            pool,
+            producer,
+            topic,
+            interval: Duration::from_secs(config.overdue_check_interval_seconds),
+        }
+    }
+
+    pub async fn run(self) {
+        tracing::info!(interval = ?self.interval, topic = %self.topic, "overdue task notifier started");
+
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.notify_all().await {
+                tracing::error!(error = %e, "overdue task notification failed");
+            }
+        }
+    }
+
+    async fn notify_all(&self) -> Result<()> {
+        let tenant_ids = sqlx::query_scalar::<_, Uuid>("SELECT id FROM tenants")
+            .fetch_all(&self.pool)
+            .await?;
+
+        for tenant_id in tenant_ids {
+            loop {
+                match self.notify_batch(tenant_id).await {
+                    Ok(notified) if notified < NOTIFY_BATCH_SIZE as usize => break,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        tracing::error!(%tenant_id, error = %e, "overdue task notification failed");
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Publishes up to `NOTIFY_BATCH_SIZE` of the tenant's notifications and
+    /// marks the delivered ones. The tasks are claimed in a transaction of
+    /// their own, so concurrent instances skip them rather than notify twice
+    /// without rows staying locked during delivery; undelivered ones are
+    /// released and retried on the next tick. Returns the number delivered.
+    async fn notify_batch(&self, tenant_id: Uuid) -> Result<usize> {
+        let claimed_at = Utc::now();
+        let mut tx = db::begin_tenant(&self.pool, tenant_id).await?;
+        let mut tasks = sqlx::query_as::<_, Task>(&format!(
+            r#"
+            UPDATE tasks SET overdue_claimed_at = $2
+            WHERE id IN (
+                SELECT id FROM tasks
+                WHERE status = 'open' AND overdue_notified_at IS NULL AND due_at <= NOW()
+                  AND (overdue_claimed_at IS NULL
+                       OR overdue_claimed_at < $2 - make_interval(secs => $3))
+                ORDER BY due_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            TASK_COLUMNS
+        ))
+        .bind(NOTIFY_BATCH_SIZE)
+        .bind(claimed_at)
+        .bind(CLAIM_LEASE.as_secs_f64())
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        if tasks.is_empty() {
+            return Ok(0);
+        }
+
+        let notified_at = Utc::now();
+        for task in &mut tasks {
+            task.overdue_notified_at = Some(notified_at);
+        }
+        let results = join_all(tasks.iter().map(|task| self.publish(task))).await;
+
+        let mut delivered = Vec::with_capacity(tasks.len());
+        for (result, task) in results.into_iter().zip(&tasks) {
+            match result {
+                Ok(()) => delivered.push(task.id),
+                Err(e) => tracing::error!(
+                    task_id = %task.id,
+                    topic = %self.topic,
+                    error = %e,
+                    "failed to publish overdue task notification"
+                ),
+            }
+        }
+
+        // Tasks rescheduled meanwhile lost the claim and are left to notify
+        // again for their new due date
+        let claimed: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+        let mut tx = db::begin_tenant(&self.pool, tenant_id).await?;
+        sqlx::query(
+            r#"
+            UPDATE tasks
+            SET overdue_claimed_at = NULL,
+                overdue_notified_at = CASE WHEN id = ANY($3) THEN $4 ELSE overdue_notified_at END
+            WHERE id = ANY($1) AND overdue_claimed_at = $2
+            "#,
+        )
+        .bind(&claimed)
+        .bind(claimed_at)
+        .bind(&delivered)
+        .bind(notified_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        tracing::debug!(notified = delivered.len(), "overdue tasks notified");
+        Ok(delivered.len())
+    }
+
+    async fn publish(&self, task: &Task) -> Result<()> {
+        let payload = encode(task)?;
+        let key = record_key(task);
+        let record = FutureRecord::to(&self.topic).key(&key).payload(&payload);
+
+        self.producer
+            .send(record, QUEUE_TIMEOUT)
+            .await
+            .map_err(|(e, _)| {
+                metrics::record_kafka_delivery_failure(&self.topic);
+                AppError::Kafka(e)
+            })?;
+        Ok(())
+    }
+}
+
+async fn ensure_assignee(pool: &PgPool, tenant_id: Uuid, assignee_id: Uuid) -> Result<()> {
+    let mut tx = db::begin_tenant(pool, tenant_id).await?;
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND tenant_id = $2)")
+            .bind(assignee_id)
+            .bind(tenant_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    tx.commit().await?;
+    if !exists {
+        return Err(AppError::Validation(format!(
+            "User {} not found",
+            assignee_id
+        )));
+    }
+    Ok(())
+}
+
+fn record_key(task: &Task) -> String {
+    format!("{}:{}", task.tenant_id, task.assignee_id)
+}
+
+fn encode(task: &Task) -> Result<Vec<u8>> {
+    serde_json::to_vec(task)
+        .map_err(|e| AppError::Internal(format!("Failed to encode task: {}", e)))
+}
+
+fn not_found(id: Uuid) -> AppError {
+    AppError::NotFound(format!("Task {} not found", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TaskStatus;
+
+    #[test]
+    fn test_encode_overdue_notification() {
+        let task = Task {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            title: "Send proposal".to_string(),
+            description: None,
+            due_at: Utc::now(),
+            assignee_id: Uuid::new_v4(),
+            status: TaskStatus::Open,
+            completed_at: None,
+            overdue_notified_at: Some(Utc::now()),
+            contact_id: None,
+            account_id: None,
+            deal_id: Some(Uuid::new_v4()),
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let decoded: Task = serde_json::from_slice(&encode(&task).unwrap()).unwrap();
+        assert_eq!(decoded.id, task.id);
+        assert_eq!(decoded.status, TaskStatus::Open);
+        assert_eq!(decoded.deal_id, task.deal_id);
+        assert_eq!(
+            record_key(&task),
+            format!("{}:{}", task.tenant_id, task.assignee_id)
+        );
+    }
+}